@@ -0,0 +1,45 @@
+use ant_colony_simulation::{Scenario, World};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+//four colonies on a large open map with enough food that nobody starves.
+fn crowded_world(ants: u32) -> World {
+    let overrides = [
+        "seed=7".to_string(),
+        "width=400".to_string(),
+        "height=200".to_string(),
+        "colonies=[{x=100,y=50},{x=300,y=50},{x=100,y=150},{x=300,y=150}]".to_string(),
+        format!("lifecycle.initial_workers={}", ants / 4),
+        "lifecycle.initial_food=10000000".to_string(),
+        "food_spawn_rate=1.0".to_string(),
+    ];
+
+    let mut world = Scenario::parse("", &overrides)
+        .and_then(|scenario| scenario.build())
+        .expect("benchmark scenario is valid");
+
+    //let trails and food sources build up so ants have something to follow.
+    world.run(50);
+    world
+}
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.sample_size(20);
+
+    for ants in [1_000, 10_000, 20_000] {
+        let world = crowded_world(ants);
+        group.throughput(Throughput::Elements(world.ants.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(ants), &world, |b, world| {
+            b.iter_batched(
+                || world.clone(),
+                |mut world| world.step(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
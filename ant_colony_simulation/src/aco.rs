@@ -0,0 +1,264 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::map::Terrain;
+use crate::pheromone::{PheromoneField, PheromoneType};
+use crate::position::Position;
+
+/// Tuning knobs for ant-colony-optimisation movement.
+///
+/// An ant picks its next cell among its neighbours with probability
+/// proportional to `(base_concentration + pheromone)^alpha * heuristic^beta`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AcoParams {
+    /// How strongly ants trust existing trails.
+    pub alpha: f32,
+    /// How strongly ants trust the heuristic (distance to their goal).
+    pub beta: f32,
+    /// Share of every cell's concentration lost per tick.
+    pub evaporation_rate: f32,
+    /// Share of every cell's concentration handed to its neighbours per tick.
+    pub diffusion_rate: f32,
+    /// Pheromone laid per step at the start of a trip. Later steps lay less,
+    /// so shorter paths end up with the stronger trails.
+    pub deposit_amount: f32,
+    /// Weight every cell has without any pheromone, so ants keep exploring.
+    pub base_concentration: f32,
+    /// Multiplier applied to the cell the ant just left.
+    pub backtrack_penalty: f32,
+    /// How far (in cells) a wandering ant can smell food.
+    pub food_sense_radius: u16,
+    /// Ticks a to-home trail lasts after its last deposit.
+    pub to_home_duration: u32,
+    /// Ticks a to-food trail lasts after its last deposit.
+    pub to_food_duration: u32,
+}
+
+impl Default for AcoParams {
+    fn default() -> Self {
+        AcoParams {
+            alpha: 1.0,
+            beta: 2.0,
+            evaporation_rate: 0.005,
+            diffusion_rate: 0.005,
+            deposit_amount: 1.0,
+            base_concentration: 0.01,
+            backtrack_penalty: 0.05,
+            food_sense_radius: 2,
+            to_home_duration: 60,
+            to_food_duration: 120,
+        }
+    }
+}
+
+impl AcoParams {
+    /// Concentration laid by an ant that is `trip_length` steps into its trip.
+    pub fn deposit_for(&self, trip_length: u32) -> f32 {
+        self.deposit_amount / (1.0 + trip_length as f32 / 50.0)
+    }
+
+    /// How long a freshly laid trail of `pheromone_type` lasts.
+    pub fn duration(&self, pheromone_type: PheromoneType) -> u32 {
+        match pheromone_type {
+            PheromoneType::ToHome => self.to_home_duration,
+            PheromoneType::ToFood => self.to_food_duration,
+        }
+    }
+}
+
+/// Everything an ant needs to pick its next cell.
+pub struct Choice {
+    pub position: Position,
+    pub previous: Option<Position>,
+    /// Trail the ant is looking for.
+    pub follow: PheromoneType,
+    /// Where the ant wants to go, if it knows.
+    pub goal: Option<Position>,
+    /// Where the ant wants to get away from when it has no goal.
+    pub away_from: Option<Position>,
+}
+
+/// Rolls the weighted neighbour selection. Rough cells are picked less often
+/// in proportion to their movement cost. Returns `None` only when the ant is
+/// walled in.
+pub fn choose_next<R: Rng + ?Sized>(
+    terrain: &Terrain,
+    pheromones: &PheromoneField,
+    params: &AcoParams,
+    choice: &Choice,
+    rng: &mut R,
+) -> Option<Position> {
+    let mut candidates = [(choice.position, 0.0f64); 8];
+    let mut count = 0;
+    let mut total = 0.0f64;
+
+    for neighbour in terrain.neighbours(choice.position) {
+        let trail = params.base_concentration + pheromones.concentration(neighbour, choice.follow);
+        //1 for a step towards the goal, 1/2 sideways and 1/3 away from it.
+        let heuristic = match choice.goal {
+            Some(goal) => {
+                let progress =
+                    i32::from(neighbour.distance(goal)) - i32::from(choice.position.distance(goal));
+                1.0 / (2 + progress) as f32
+            }
+            //without a goal, head away from the cell we want to leave behind.
+            None => match choice.away_from {
+                Some(origin) => {
+                    let progress = i32::from(choice.position.distance(origin))
+                        - i32::from(neighbour.distance(origin));
+                    1.0 / (2 + progress) as f32
+                }
+                None => 1.0,
+            },
+        };
+
+        let cost = f32::from(terrain.get(neighbour).movement_cost());
+        let mut weight = f64::from(trail.powf(params.alpha) * heuristic.powf(params.beta) / cost);
+        if Some(neighbour) == choice.previous {
+            weight *= f64::from(params.backtrack_penalty);
+        }
+
+        candidates[count] = (neighbour, weight);
+        count += 1;
+        total += weight;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    if total <= 0.0 || !total.is_finite() {
+        return Some(candidates[rng.random_range(0..count)].0);
+    }
+
+    let mut roll = rng.random::<f64>() * total;
+    for &(neighbour, weight) in &candidates[..count] {
+        if roll < weight {
+            return Some(neighbour);
+        }
+        roll -= weight;
+    }
+
+    Some(candidates[count - 1].0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+    use crate::pheromone::Pheromone;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    fn wander(position: Position) -> Choice {
+        Choice {
+            position,
+            previous: None,
+            follow: PheromoneType::ToFood,
+            goal: None,
+            away_from: None,
+        }
+    }
+
+    //how often each cell is picked in `draws` rolls.
+    fn picks(
+        terrain: &Terrain,
+        field: &PheromoneField,
+        params: &AcoParams,
+        choice: &Choice,
+        draws: usize,
+    ) -> HashMap<Position, usize> {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut counts = HashMap::new();
+        for _ in 0..draws {
+            let next = choose_next(terrain, field, params, choice, &mut rng).unwrap();
+            *counts.entry(next).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn never_steps_out_of_the_world_or_into_a_wall() {
+        let mut terrain = Terrain::open(10, 10);
+        for y in 1..10 {
+            terrain.set(Position::new(3, y), Tile::Wall);
+        }
+        terrain.set(Position::new(2, 2), Tile::Water);
+        let field = PheromoneField::new(10, 10);
+        let params = AcoParams::default();
+
+        for start in [
+            Position::new(1, 1),
+            Position::new(9, 9),
+            Position::new(2, 5),
+        ] {
+            for next in picks(&terrain, &field, &params, &wander(start), 500).keys() {
+                assert!((1..10).contains(&next.x) && (1..10).contains(&next.y));
+                assert!(terrain.is_passable(*next));
+                assert_eq!(next.distance(start), 1);
+            }
+        }
+
+        //walled in on every side.
+        let mut rng = StdRng::seed_from_u64(5);
+        let cramped = Terrain::open(2, 2);
+        let choice = wander(Position::new(1, 1));
+        assert_eq!(
+            choose_next(&cramped, &field, &params, &choice, &mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn a_stronger_trail_is_picked_more_often() {
+        let mut field = PheromoneField::new(10, 10);
+        for (x, concentration) in [(6, 1.0), (4, 0.5)] {
+            field.deposit(Pheromone {
+                concentration,
+                ..Pheromone::new(Position::new(x, 5), PheromoneType::ToFood, 120)
+            });
+        }
+
+        let counts = picks(
+            &Terrain::open(10, 10),
+            &field,
+            &AcoParams::default(),
+            &wander(Position::new(5, 5)),
+            2000,
+        );
+        let count = |x, y| counts.get(&Position::new(x, y)).copied().unwrap_or(0);
+
+        assert!(count(6, 5) > count(4, 5));
+        assert!(count(4, 5) > count(5, 4));
+        assert!(count(4, 5) > count(5, 6));
+    }
+
+    #[test]
+    fn without_alpha_and_beta_every_neighbour_is_as_likely() {
+        let mut field = PheromoneField::new(10, 10);
+        field.deposit(Pheromone::new(
+            Position::new(6, 5),
+            PheromoneType::ToFood,
+            120,
+        ));
+        let params = AcoParams {
+            alpha: 0.0,
+            beta: 0.0,
+            backtrack_penalty: 1.0,
+            ..AcoParams::default()
+        };
+        let choice = Choice {
+            goal: Some(Position::new(9, 5)),
+            ..wander(Position::new(5, 5))
+        };
+
+        let counts = picks(&Terrain::open(10, 10), &field, &params, &choice, 8000);
+
+        assert_eq!(counts.len(), 8);
+        for count in counts.values() {
+            assert!((800..1200).contains(count), "{} of 8000", count);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::aco::{self, AcoParams, Choice};
+use crate::colony::Colony;
+use crate::food::Food;
+use crate::map::Terrain;
+use crate::pheromone::{Pheromone, PheromoneField, PheromoneType};
+use crate::position::Position;
+use crate::predator::Predator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AntType {
+    Soldier,
+    Worker,
+}
+
+impl AntType {
+    pub fn max_health(self) -> u32 {
+        match self {
+            AntType::Soldier => 10,
+            AntType::Worker => 2,
+        }
+    }
+
+    /// Damage dealt to a predator per tick. Workers only run.
+    pub fn damage(self) -> u32 {
+        match self {
+            AntType::Soldier => 3,
+            AntType::Worker => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeathCause {
+    OldAge,
+    Starvation,
+    Predator,
+    /// Killed by a soldier of a rival colony.
+    Combat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AntMode {
+    Wandering,
+    Returning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ant {
+    /// Unique within the world for the whole run.
+    pub id: u64,
+    /// Id of the colony the ant belongs to.
+    pub colony: usize,
+    pub position: Position,
+    pub ant_type: AntType,
+    pub moves: u16,
+    pub lifespan: u32,
+    /// Ticks the ant goes between meals.
+    pub starvation: u32,
+    /// Ticks since the ant last ate.
+    pub hunger: u32,
+    pub food_in_hand: bool,
+    pub mode: AntMode,
+    pub is_alive: bool,
+    pub time_elapsed: u32,
+    pub explored_pheromones: Option<Pheromone>,
+    pub trip_length: u32,
+    pub health: u32,
+    pub kills: u32,
+    /// Ticks left before the ant can move again, e.g. after entering rough terrain.
+    pub delay: u8,
+    pub cause_of_death: Option<DeathCause>,
+}
+
+impl Ant {
+    pub fn new(
+        initial_position: Position,
+        ant_type: AntType,
+        moves: u16,
+        lifespan: u32,
+        starvation: u32,
+        food_in_hand: bool,
+        mode: AntMode,
+    ) -> Self {
+        Ant {
+            id: 0,
+            colony: 0,
+            position: initial_position,
+            ant_type,
+            moves,
+            lifespan,
+            starvation,
+            hunger: 0,
+            food_in_hand,
+            mode,
+            is_alive: true,
+            time_elapsed: 0,
+            explored_pheromones: None,
+            trip_length: 0,
+            health: ant_type.max_health(),
+            kills: 0,
+            delay: 0,
+            cause_of_death: None,
+        }
+    }
+
+    /// Marks the ant dead. The first cause sticks.
+    pub fn die(&mut self, cause: DeathCause) {
+        if self.is_alive {
+            self.is_alive = false;
+            self.cause_of_death = Some(cause);
+        }
+    }
+}
+
+/// The read-only parts of the world an ant reacts to.
+pub(crate) struct Surroundings<'a> {
+    pub terrain: &'a Terrain,
+    /// Id of the colony whose ant is moving.
+    pub colony: usize,
+    pub colony_position: Position,
+    /// Owner and position of every living ant at the start of the tick.
+    pub ant_positions: &'a [(usize, Position)],
+    /// Whether soldiers go after ants of rival colonies.
+    pub warfare: bool,
+    pub foods: &'a VecDeque<Food>,
+    pub predators: &'a VecDeque<Predator>,
+    /// Trails of the ant's own colony.
+    pub pheromones: &'a PheromoneField,
+    pub params: &'a AcoParams,
+}
+
+/// What an ant is going to do this tick. Decisions are made against a
+/// read-only view of the world, so all ants can decide at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Decision {
+    /// Walled in, or nowhere it wants to go.
+    Stay,
+    /// Step onto a neighbouring cell.
+    Move(Position),
+    /// Drop the carried food at the colony and set out again.
+    Deliver,
+}
+
+/// Works out the ant's next move without changing anything.
+pub(crate) fn decide<R: Rng + ?Sized>(
+    ant: &Ant,
+    surroundings: &Surroundings,
+    rng: &mut R,
+) -> Decision {
+    match (ant.ant_type, ant.mode) {
+        (AntType::Soldier, _) => soldier_ant(ant, surroundings, rng),
+        (AntType::Worker, AntMode::Wandering) => wandering_ant(ant, surroundings, rng),
+        (AntType::Worker, AntMode::Returning) => returning_ant(ant, surroundings, rng),
+    }
+}
+
+/// Carries out a decision. Workers lay their trail on every cell they step
+/// onto: searching ants a to-home trail, carriers a to-food trail. Returns
+/// whether food was delivered.
+pub(crate) fn act(
+    ant: &mut Ant,
+    decision: Decision,
+    pheromones: &mut PheromoneField,
+    colony_position: Position,
+    params: &AcoParams,
+) -> bool {
+    match decision {
+        Decision::Stay => false,
+        Decision::Move(next) if ant.ant_type == AntType::Soldier => {
+            ant.position = next;
+            false
+        }
+        Decision::Move(next) => {
+            let lay = match ant.mode {
+                AntMode::Wandering => PheromoneType::ToHome,
+                AntMode::Returning => PheromoneType::ToFood,
+            };
+            let duration = params.duration(lay);
+
+            ant.explored_pheromones = pheromones
+                .get(ant.position, lay)
+                .or(Some(Pheromone::new(ant.position, lay, duration)));
+            ant.position = next;
+            ant.trip_length = ant.trip_length.saturating_add(1);
+
+            pheromones.deposit(Pheromone {
+                concentration: params.deposit_for(ant.trip_length),
+                ..Pheromone::new(ant.position, lay, duration)
+            });
+            false
+        }
+        Decision::Deliver => {
+            let delivered = ant.food_in_hand;
+
+            ant.position = colony_position;
+            ant.mode = AntMode::Wandering;
+            ant.food_in_hand = false;
+            ant.trip_length = 0;
+            ant.explored_pheromones = None;
+            delivered
+        }
+    }
+}
+
+//rolls the next cell following `choice`.
+fn take_step<R: Rng + ?Sized>(
+    surroundings: &Surroundings,
+    choice: Choice,
+    rng: &mut R,
+) -> Decision {
+    let terrain = surroundings.terrain;
+    let pheromones = surroundings.pheromones;
+
+    match aco::choose_next(terrain, pheromones, surroundings.params, &choice, rng) {
+        Some(next) => Decision::Move(next),
+        None => Decision::Stay,
+    }
+}
+
+/// A searching ant follows the to-food trails, drifting away from the
+/// colony. Food within `food_sense_radius` pulls it in directly.
+fn wandering_ant<R: Rng + ?Sized>(ant: &Ant, surroundings: &Surroundings, rng: &mut R) -> Decision {
+    let radius = surroundings.params.food_sense_radius;
+    let smelt_food = surroundings
+        .foods
+        .iter()
+        .filter(|food| food.food_size > 0)
+        .map(|food| food.position)
+        .filter(|position| ant.position.distance(*position) <= radius)
+        .min_by_key(|position| ant.position.distance(*position));
+
+    let choice = Choice {
+        position: ant.position,
+        previous: ant.explored_pheromones.map(|pheromone| pheromone.position),
+        follow: PheromoneType::ToFood,
+        goal: smelt_food,
+        away_from: Some(surroundings.colony_position),
+    };
+
+    take_step(surroundings, choice, rng)
+}
+
+/// A searching ant standing on food takes one unit and turns for home.
+/// Ants that already carry food walk over it.
+pub(crate) fn check_ant_found_food(ant: &mut Ant, foods: &mut VecDeque<Food>) {
+    if ant.food_in_hand {
+        return;
+    }
+
+    let Some(food) = foods
+        .iter_mut()
+        .find(|food| food.position == ant.position && food.food_size > 0)
+    else {
+        return;
+    };
+
+    if food.take() {
+        //turn around: the way back starts a fresh trip.
+        ant.trip_length = 0;
+        ant.explored_pheromones = None;
+        ant.mode = AntMode::Returning;
+        ant.food_in_hand = true;
+    }
+}
+
+/// How far from the colony soldiers patrol and engage predators.
+pub const GUARD_RADIUS: u16 = 8;
+
+/// A soldier charges the nearest threat close to itself or to the colony: a
+/// predator or, when colonies are at war, an ant of a rival colony. Without
+/// one it patrols around the colony.
+fn soldier_ant<R: Rng + ?Sized>(ant: &Ant, surroundings: &Surroundings, rng: &mut R) -> Decision {
+    let terrain = surroundings.terrain;
+    let colony_position = surroundings.colony_position;
+    let in_range = |position: &Position| {
+        position.distance(colony_position) <= GUARD_RADIUS
+            || position.distance(ant.position) <= GUARD_RADIUS
+    };
+
+    let predators = surroundings
+        .predators
+        .iter()
+        .filter(|predator| predator.is_alive)
+        .map(|predator| predator.position);
+    let rivals = surroundings
+        .ant_positions
+        .iter()
+        .filter(|_| surroundings.warfare)
+        .filter(|(colony, _)| *colony != surroundings.colony)
+        .map(|(_, position)| *position);
+
+    let target = predators
+        .chain(rivals)
+        .filter(in_range)
+        .min_by_key(|position| position.distance(ant.position));
+
+    if let Some(target) = target {
+        return Decision::Move(terrain.step_towards(ant.position, target));
+    }
+
+    if ant.position.distance(colony_position) >= GUARD_RADIUS {
+        return Decision::Move(terrain.step_towards(ant.position, colony_position));
+    }
+
+    let patrol: Vec<Position> = terrain
+        .neighbours(ant.position)
+        .filter(|neighbour| neighbour.distance(colony_position) <= GUARD_RADIUS)
+        .collect();
+    if patrol.is_empty() {
+        return Decision::Stay;
+    }
+
+    Decision::Move(patrol[rng.random_range(0..patrol.len())])
+}
+
+/// Hits the first living predator within reach. Returns whether it found one.
+pub(crate) fn strike(ant: &mut Ant, predators: &mut VecDeque<Predator>) -> bool {
+    let damage = ant.ant_type.damage();
+    if damage == 0 || !ant.is_alive {
+        return false;
+    }
+
+    let target = predators
+        .iter_mut()
+        .find(|predator| predator.is_alive && predator.position.distance(ant.position) <= 1);
+
+    if let Some(predator) = target {
+        predator.health = predator.health.saturating_sub(damage);
+
+        if predator.health == 0 {
+            predator.is_alive = false;
+            ant.kills += 1;
+        }
+        return true;
+    }
+
+    false
+}
+
+/// Ages the ant and feeds it from the store when a meal is due. An ant dies
+/// of old age at the end of its lifespan, or of hunger if the store is empty.
+pub(crate) fn metabolise(ant: &mut Ant, colony: &mut Colony) {
+    ant.time_elapsed = ant.time_elapsed.saturating_add(1);
+    ant.hunger = ant.hunger.saturating_add(1);
+
+    if ant.time_elapsed >= ant.lifespan {
+        ant.die(DeathCause::OldAge);
+        return;
+    }
+
+    if ant.hunger >= ant.starvation {
+        if colony.eat(1) {
+            ant.hunger = 0;
+        } else {
+            ant.die(DeathCause::Starvation);
+        }
+    }
+}
+
+/// A returning ant heads for the colony, guided by the to-home trails. Once
+/// next to it, it goes in to drop its food.
+fn returning_ant<R: Rng + ?Sized>(ant: &Ant, surroundings: &Surroundings, rng: &mut R) -> Decision {
+    let colony_position = surroundings.colony_position;
+
+    //check if the colony is nearby.
+    if ant.position.distance(colony_position) <= 1 {
+        return Decision::Deliver;
+    }
+
+    let choice = Choice {
+        position: ant.position,
+        previous: ant.explored_pheromones.map(|pheromone| pheromone.position),
+        follow: PheromoneType::ToHome,
+        goal: Some(colony_position),
+        away_from: None,
+    };
+
+    take_step(surroundings, choice, rng)
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+
+use crate::aco::AcoParams;
+use crate::ant::AntType;
+use crate::pheromone::PheromoneField;
+use crate::position::Position;
+
+/// Colours handed out to colonies in the order they are created.
+pub const COLONY_COLORS: [(u8, u8, u8); 6] = [
+    (220, 50, 47),
+    (38, 139, 210),
+    (133, 153, 0),
+    (181, 137, 0),
+    (211, 54, 130),
+    (42, 161, 152),
+];
+
+/// Knobs for the colony economy and the ants' life cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LifecycleParams {
+    /// Workers the colony starts with.
+    pub initial_workers: u32,
+    /// Food in the store at the start.
+    pub initial_food: u32,
+    pub worker_lifespan: u32,
+    pub soldier_lifespan: u32,
+    /// Ticks an adult goes between meals. An ant that is due for a meal
+    /// while the store is empty starves.
+    pub starvation: u32,
+    /// Ticks between two eggs of the queen.
+    pub egg_interval: u32,
+    /// Food the queen needs to lay an egg.
+    pub egg_cost: u32,
+    /// Ticks until an egg becomes a larva.
+    pub egg_ticks: u32,
+    /// Ticks until a larva becomes an adult.
+    pub larva_ticks: u32,
+    /// Ticks between two meals of a larva.
+    pub larva_meal_interval: u32,
+}
+
+impl Default for LifecycleParams {
+    fn default() -> Self {
+        LifecycleParams {
+            initial_workers: 10,
+            initial_food: 50,
+            worker_lifespan: 1000,
+            soldier_lifespan: 1500,
+            starvation: 500,
+            egg_interval: 10,
+            egg_cost: 1,
+            egg_ticks: 30,
+            larva_ticks: 60,
+            larva_meal_interval: 30,
+        }
+    }
+}
+
+impl LifecycleParams {
+    pub fn lifespan(&self, ant_type: AntType) -> u32 {
+        match ant_type {
+            AntType::Soldier => self.soldier_lifespan,
+            AntType::Worker => self.worker_lifespan,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BroodStage {
+    Egg,
+    Larva,
+}
+
+/// An egg or larva waiting in the nest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Brood {
+    pub stage: BroodStage,
+    /// Ticks spent in the current stage.
+    pub age: u32,
+    /// Ticks since the larva was last fed.
+    pub hunger: u32,
+}
+
+/// A nest with its queen, store and brood. Every colony lays and follows only
+/// its own pheromones and can be tuned independently, so several of them can
+/// compete for the same food.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Colony {
+    /// Index of the colony in `World::colonies`, also stored on its ants.
+    pub id: usize,
+    pub position: Position,
+    /// RGB colour used when drawing the colony and its ants.
+    pub color: (u8, u8, u8),
+    /// Food in the store.
+    pub food_amount: u32,
+    /// Food brought home over the whole run.
+    pub food_delivered: u32,
+    pub brood: Vec<Brood>,
+    /// Ticks since the queen last laid an egg.
+    pub queen_rest: u32,
+    pub pheromones: PheromoneField,
+    pub aco: AcoParams,
+    pub lifecycle: LifecycleParams,
+}
+
+impl Colony {
+    /// A colony in a `width` x `height` world, with `lifecycle.initial_food` in store.
+    pub fn new(
+        id: usize,
+        position: Position,
+        width: u16,
+        height: u16,
+        aco: AcoParams,
+        lifecycle: LifecycleParams,
+    ) -> Self {
+        Colony {
+            id,
+            position,
+            color: COLONY_COLORS[id % COLONY_COLORS.len()],
+            food_amount: lifecycle.initial_food,
+            food_delivered: 0,
+            brood: Vec::new(),
+            queen_rest: 0,
+            pheromones: PheromoneField::new(width, height),
+            aco,
+            lifecycle,
+        }
+    }
+
+    /// Stores food carried home by a worker.
+    pub fn deposit(&mut self, amount: u32) {
+        self.food_amount += amount;
+        self.food_delivered += amount;
+    }
+
+    /// Takes `amount` out of the store if there is enough of it.
+    pub fn eat(&mut self, amount: u32) -> bool {
+        if self.food_amount < amount {
+            return false;
+        }
+
+        self.food_amount -= amount;
+        true
+    }
+
+    /// One tick of the nest: the queen lays when she is rested and the store
+    /// can feed every adult once more, eggs hatch, larvae are fed (or die of
+    /// hunger) and grow. Returns how many larvae became adults.
+    pub fn tend_brood(&mut self, adults: usize) -> usize {
+        let params = self.lifecycle;
+
+        self.queen_rest = self.queen_rest.saturating_add(1);
+
+        let reserve = adults as u32 + self.brood.len() as u32;
+        if self.queen_rest >= params.egg_interval && self.food_amount >= reserve + params.egg_cost {
+            self.food_amount -= params.egg_cost;
+            self.queen_rest = 0;
+            self.brood.push(Brood {
+                stage: BroodStage::Egg,
+                age: 0,
+                hunger: 0,
+            });
+        }
+
+        let mut matured = 0;
+        let mut store = self.food_amount;
+
+        self.brood.retain_mut(|brood| {
+            brood.age += 1;
+
+            match brood.stage {
+                BroodStage::Egg => {
+                    if brood.age >= params.egg_ticks {
+                        brood.stage = BroodStage::Larva;
+                        brood.age = 0;
+                    }
+                    true
+                }
+                BroodStage::Larva => {
+                    brood.hunger += 1;
+                    if brood.hunger >= params.larva_meal_interval {
+                        if store == 0 {
+                            return false;
+                        }
+                        store -= 1;
+                        brood.hunger = 0;
+                    }
+
+                    if brood.age >= params.larva_ticks {
+                        matured += 1;
+                        return false;
+                    }
+                    true
+                }
+            }
+        });
+
+        self.food_amount = store;
+        matured
+    }
+
+    /// A colony with no adults, no brood and no food to lay an egg is done.
+    pub fn has_collapsed(&self, adults: usize) -> bool {
+        adults == 0 && self.brood.is_empty() && self.food_amount < self.lifecycle.egg_cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ant::{self, Ant, AntMode, DeathCause};
+
+    fn colony(food: u32) -> Colony {
+        let lifecycle = LifecycleParams {
+            initial_food: food,
+            egg_interval: 1,
+            egg_cost: 2,
+            egg_ticks: 2,
+            larva_ticks: 3,
+            larva_meal_interval: 100,
+            ..LifecycleParams::default()
+        };
+        Colony::new(
+            0,
+            Position::new(5, 5),
+            10,
+            10,
+            AcoParams::default(),
+            lifecycle,
+        )
+    }
+
+    #[test]
+    fn food_pays_for_eggs() {
+        let mut colony = colony(5);
+
+        colony.tend_brood(0);
+        assert_eq!(colony.brood.len(), 1);
+        assert_eq!(colony.food_amount, 3);
+
+        //one meal each for three adults and the egg has to stay in store.
+        colony.tend_brood(3);
+        assert_eq!(colony.brood.len(), 1);
+        assert_eq!(colony.food_amount, 3);
+    }
+
+    #[test]
+    fn eggs_hatch_and_larvae_grow_up() {
+        let mut colony = colony(2);
+
+        let matured: Vec<usize> = (0..6).map(|_| colony.tend_brood(0)).collect();
+
+        //laid on the first tick, a larva from the second, an adult on the fifth.
+        assert_eq!(matured, vec![0, 0, 0, 0, 1, 0]);
+        assert!(colony.brood.is_empty());
+        assert!(colony.has_collapsed(0));
+    }
+
+    #[test]
+    fn hungry_larvae_die_when_the_store_is_empty() {
+        let mut colony = colony(0);
+        colony.brood.push(Brood {
+            stage: BroodStage::Larva,
+            age: 0,
+            hunger: 99,
+        });
+
+        assert_eq!(colony.tend_brood(0), 0);
+        assert!(colony.brood.is_empty());
+    }
+
+    #[test]
+    fn ants_eat_from_the_store_until_it_is_empty() {
+        let mut colony = colony(1);
+        let mut ant = Ant::new(
+            colony.position,
+            AntType::Worker,
+            1,
+            100,
+            3,
+            false,
+            AntMode::Wandering,
+        );
+
+        for _ in 0..5 {
+            ant::metabolise(&mut ant, &mut colony);
+        }
+        assert!(ant.is_alive);
+        assert_eq!(colony.food_amount, 0);
+
+        ant::metabolise(&mut ant, &mut colony);
+        assert!(!ant.is_alive);
+        assert_eq!(ant.cause_of_death, Some(DeathCause::Starvation));
+    }
+}
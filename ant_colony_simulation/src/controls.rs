@@ -0,0 +1,108 @@
+use ant_colony_simulation::Position;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
+use std::time::Duration;
+
+use crate::render::CELL_COLUMNS;
+
+const MIN_TICK: Duration = Duration::from_millis(10);
+const MAX_TICK: Duration = Duration::from_millis(5000);
+
+/// Key bindings, shown in the help line.
+pub const HELP: &str = "space pause  n step  +/- speed  h trails  \
+    left click inspect ant / drop food  right click wall  q quit";
+
+/// What the main loop has to do in response to an input event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Advance the world by one tick.
+    Step,
+    /// Inspect the ant at the cell, or drop food there if there is none.
+    Click(Position),
+    /// Raise a wall at the cell.
+    Wall(Position),
+    /// Nothing changed in the world, but the screen is out of date.
+    Redraw,
+}
+
+/// State of the interactive front end.
+pub struct Controls {
+    pub paused: bool,
+    /// Time between two ticks while running.
+    pub tick: Duration,
+    pub show_pheromones: bool,
+    /// Id of the ant whose fields are shown.
+    pub inspected: Option<u64>,
+    pub quit: bool,
+}
+
+impl Controls {
+    pub fn new(tick: Duration) -> Self {
+        Controls {
+            paused: false,
+            tick,
+            show_pheromones: true,
+            inspected: None,
+            quit: false,
+        }
+    }
+
+    /// Updates the controls from a terminal event and tells what else needs
+    /// doing, if anything.
+    pub fn handle(&mut self, event: Event) -> Option<Action> {
+        match event {
+            Event::Key(key) => self.key(key),
+            Event::Mouse(mouse) => {
+                let position = Position::new(mouse.column / CELL_COLUMNS, mouse.row);
+
+                match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => Some(Action::Click(position)),
+                    MouseEventKind::Down(MouseButton::Right) => Some(Action::Wall(position)),
+                    _ => None,
+                }
+            }
+            Event::Resize(_, _) => Some(Action::Redraw),
+            _ => None,
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                None
+            }
+            KeyCode::Char(' ') | KeyCode::Char('p') => {
+                self.paused = !self.paused;
+                Some(Action::Redraw)
+            }
+            //stepping pauses, so the world stays on the step just taken.
+            KeyCode::Char('n') | KeyCode::Right => {
+                self.paused = true;
+                Some(Action::Step)
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.tick = (self.tick / 2).max(MIN_TICK);
+                Some(Action::Redraw)
+            }
+            KeyCode::Char('-') => {
+                self.tick = (self.tick * 2).min(MAX_TICK);
+                Some(Action::Redraw)
+            }
+            KeyCode::Char('h') => {
+                self.show_pheromones = !self.show_pheromones;
+                Some(Action::Redraw)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::map::Terrain;
+use crate::position::Position;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Food {
+    pub position: Position,
+    pub lifespan: u32,
+    /// Units left. Every ant that picks up food takes one.
+    pub food_size: u32,
+    /// Units the source started with, and that a farm grows back to.
+    pub capacity: u32,
+    /// Units a farm grows back every `FoodParams::regrow_interval` ticks. A
+    /// source that does not regrow is gone once it is empty.
+    pub regrowth: u32,
+    pub time_elapsed: u32,
+}
+
+/// How spawned food is spread over the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodDistribution {
+    /// One source at a time on a random open cell.
+    Scattered,
+    /// `cluster_size` sources at once, around a random open cell.
+    Clustered,
+}
+
+/// Sizes and lifespans of the food that turns up in the world, and how it
+/// turns up. Both ranges are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodParams {
+    pub min_lifespan: u32,
+    pub max_lifespan: u32,
+    pub min_size: u32,
+    pub max_size: u32,
+    pub distribution: FoodDistribution,
+    pub cluster_size: u32,
+    /// Largest distance of a clustered source from the centre of its patch.
+    pub cluster_radius: u16,
+    /// Size of the farms: the never-expiring sources placed by a map or
+    /// scattered at the start.
+    pub fixed_size: u32,
+    /// Farms scattered over the world at the start, on top of the map's.
+    pub farms: u32,
+    pub farm_regrowth: u32,
+    pub regrow_interval: u32,
+    /// Ticks between two seasonal bursts of food, 0 for no seasons.
+    pub season_length: u32,
+    /// Food spawns dropped at once at the start of every season.
+    pub season_burst: u32,
+}
+
+impl Default for FoodParams {
+    fn default() -> Self {
+        FoodParams {
+            min_lifespan: 30,
+            max_lifespan: 199,
+            min_size: 50,
+            max_size: 99,
+            distribution: FoodDistribution::Scattered,
+            cluster_size: 5,
+            cluster_radius: 3,
+            fixed_size: 500,
+            farms: 0,
+            farm_regrowth: 1,
+            regrow_interval: 5,
+            season_length: 0,
+            season_burst: 10,
+        }
+    }
+}
+
+impl Food {
+    /// Drops a food source of random size and lifespan at `position`.
+    pub fn new<R: Rng + ?Sized>(position: Position, params: &FoodParams, rng: &mut R) -> Self {
+        let lifespan = rng.random_range(params.min_lifespan..=params.max_lifespan);
+        let size = rng.random_range(params.min_size..=params.max_size);
+
+        Food {
+            position,
+            lifespan,
+            food_size: size,
+            capacity: size,
+            regrowth: 0,
+            time_elapsed: 0,
+        }
+    }
+
+    /// A farm, e.g. placed by a map. It never expires and grows back after
+    /// being harvested.
+    pub fn fixed(position: Position, params: &FoodParams) -> Self {
+        Food {
+            position,
+            lifespan: u32::MAX,
+            food_size: params.fixed_size,
+            capacity: params.fixed_size,
+            regrowth: params.farm_regrowth,
+            time_elapsed: 0,
+        }
+    }
+
+    /// Takes one unit, if there is any left.
+    pub fn take(&mut self) -> bool {
+        if self.food_size == 0 {
+            return false;
+        }
+
+        self.food_size -= 1;
+        true
+    }
+
+    /// Fraction of the capacity still there.
+    pub fn fullness(&self) -> f32 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+
+        self.food_size as f32 / self.capacity as f32
+    }
+
+    /// Whether the source has nothing more to give, now or later.
+    pub fn is_exhausted(&self) -> bool {
+        self.time_elapsed >= self.lifespan || (self.food_size == 0 && self.regrowth == 0)
+    }
+}
+
+/// Ages the sources, lets farms grow back and removes exhausted ones.
+pub(crate) fn update_foods(foods: &mut VecDeque<Food>, params: &FoodParams) {
+    for food in foods.iter_mut() {
+        food.time_elapsed = food.time_elapsed.saturating_add(1);
+
+        if food.regrowth > 0
+            && food
+                .time_elapsed
+                .is_multiple_of(params.regrow_interval.max(1))
+        {
+            food.food_size = food
+                .capacity
+                .min(food.food_size.saturating_add(food.regrowth));
+        }
+    }
+
+    foods.retain(|food| !food.is_exhausted());
+}
+
+/// Cells for one spawn of food: a single random open cell, or a patch of
+/// them when food comes in clusters. Cells already holding food are skipped,
+/// so a patch can come out smaller.
+pub(crate) fn spawn_positions<R: Rng + ?Sized>(
+    terrain: &Terrain,
+    foods: &VecDeque<Food>,
+    params: &FoodParams,
+    rng: &mut R,
+) -> Vec<Position> {
+    let Some(centre) = terrain.random_passable(rng) else {
+        return Vec::new();
+    };
+    let free = |position: &Position| {
+        terrain.is_passable(*position) && foods.iter().all(|food| food.position != *position)
+    };
+
+    if params.distribution == FoodDistribution::Scattered {
+        return Some(centre).filter(free).into_iter().collect();
+    }
+
+    let radius = i32::from(params.cluster_radius);
+    let mut positions: Vec<Position> = Vec::new();
+
+    for _ in 0..params.cluster_size {
+        let x = i32::from(centre.x) + rng.random_range(-radius..=radius);
+        let y = i32::from(centre.y) + rng.random_range(-radius..=radius);
+        let (Ok(x), Ok(y)) = (u16::try_from(x), u16::try_from(y)) else {
+            continue;
+        };
+        let position = Position::new(x, y);
+
+        if free(&position) && !positions.contains(&position) {
+            positions.push(position);
+        }
+    }
+
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_empty_source_gives_nothing() {
+        let mut food = Food::fixed(Position::new(1, 1), &FoodParams::default());
+        food.food_size = 1;
+
+        assert!(food.take());
+        assert!(!food.take());
+        assert_eq!(food.food_size, 0);
+    }
+
+    #[test]
+    fn farms_grow_back_to_capacity_and_apples_are_used_up() {
+        let params = FoodParams {
+            fixed_size: 3,
+            farm_regrowth: 2,
+            regrow_interval: 1,
+            ..FoodParams::default()
+        };
+        let mut farm = Food::fixed(Position::new(1, 1), &params);
+        farm.food_size = 0;
+        let mut apple = Food::fixed(Position::new(2, 2), &params);
+        apple.regrowth = 0;
+        apple.food_size = 0;
+
+        let mut foods = VecDeque::from([farm, apple]);
+        update_foods(&mut foods, &params);
+        assert_eq!(foods.len(), 1);
+        assert_eq!(foods[0].food_size, 2);
+
+        update_foods(&mut foods, &params);
+        assert_eq!(foods[0].food_size, 3);
+    }
+
+    #[test]
+    fn clusters_land_on_free_open_cells_near_each_other() {
+        use rand::SeedableRng;
+
+        let params = FoodParams {
+            distribution: FoodDistribution::Clustered,
+            cluster_size: 20,
+            cluster_radius: 2,
+            ..FoodParams::default()
+        };
+        let terrain = Terrain::open(30, 15);
+        let mut rng = rand_chacha::ChaCha12Rng::seed_from_u64(3);
+
+        for _ in 0..50 {
+            let positions = spawn_positions(&terrain, &VecDeque::new(), &params, &mut rng);
+
+            for (index, position) in positions.iter().enumerate() {
+                assert!(terrain.is_passable(*position));
+                assert!(!positions[..index].contains(position));
+                assert!(positions.iter().all(|other| other.distance(*position) <= 4));
+            }
+        }
+    }
+}
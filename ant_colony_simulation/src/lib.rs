@@ -0,0 +1,31 @@
+//! Core of the ant colony simulation. Nothing in here writes to the terminal,
+//! so a `World` can be stepped headlessly (e.g. in tests) and, given the same
+//! seed, always produces the same run.
+
+pub mod aco;
+pub mod ant;
+pub mod colony;
+pub mod food;
+pub mod map;
+pub mod pheromone;
+pub mod position;
+pub mod predator;
+pub mod scenario;
+pub mod snapshot;
+pub mod stats;
+pub mod tuning;
+pub mod world;
+
+pub use aco::AcoParams;
+pub use ant::{Ant, AntMode, AntType, DeathCause};
+pub use colony::{Brood, BroodStage, Colony, LifecycleParams};
+pub use food::{Food, FoodDistribution, FoodParams};
+pub use map::{Map, MapError, Terrain, Tile};
+pub use pheromone::{Pheromone, PheromoneField, PheromoneType};
+pub use position::Position;
+pub use predator::{Predator, PredatorMode};
+pub use scenario::{Scenario, ScenarioError};
+pub use snapshot::{Recording, SnapshotError, SNAPSHOT_VERSION};
+pub use stats::{Sample, StatsRecorder};
+pub use tuning::{Evolution, Param, Report, Trial, Tuner, TuningError};
+pub use world::{Edit, World};
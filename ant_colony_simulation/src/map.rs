@@ -0,0 +1,385 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::position::Position;
+
+/// What a single cell of the world is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tile {
+    Open,
+    Wall,
+    Water,
+    /// Passable, but entering it costs this many ticks instead of one.
+    Rough(u8),
+}
+
+impl Tile {
+    pub fn is_passable(self) -> bool {
+        matches!(self, Tile::Open | Tile::Rough(_))
+    }
+
+    /// Ticks needed to enter the tile.
+    pub fn movement_cost(self) -> u8 {
+        match self {
+            Tile::Rough(cost) => cost.max(1),
+            _ => 1,
+        }
+    }
+}
+
+/// The terrain of a `width` x `height` world. The cells at `x == 0`,
+/// `x == width`, `y == 0` and `y == height` are always walls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Terrain {
+    width: u16,
+    height: u16,
+    tiles: Vec<Tile>,
+}
+
+impl Terrain {
+    /// An empty rectangle surrounded by the border.
+    pub fn open(width: u16, height: u16) -> Self {
+        let mut terrain = Terrain {
+            width,
+            height,
+            tiles: vec![Tile::Open; (usize::from(width) + 1) * (usize::from(height) + 1)],
+        };
+
+        for x in 0..=width {
+            terrain.set(Position::new(x, 0), Tile::Wall);
+            terrain.set(Position::new(x, height), Tile::Wall);
+        }
+
+        for y in 0..=height {
+            terrain.set(Position::new(0, y), Tile::Wall);
+            terrain.set(Position::new(width, y), Tile::Wall);
+        }
+
+        terrain
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        if position.x > self.width || position.y > self.height {
+            return None;
+        }
+
+        Some(usize::from(position.y) * (usize::from(self.width) + 1) + usize::from(position.x))
+    }
+
+    /// Anything outside the world reads as a wall.
+    pub fn get(&self, position: Position) -> Tile {
+        self.index(position)
+            .map_or(Tile::Wall, |index| self.tiles[index])
+    }
+
+    /// Changes a tile. The border cannot be changed.
+    pub fn set(&mut self, position: Position, tile: Tile) {
+        let on_border = position.x == 0
+            || position.y == 0
+            || position.x >= self.width
+            || position.y >= self.height;
+
+        if let Some(index) = self.index(position) {
+            self.tiles[index] = if on_border { Tile::Wall } else { tile };
+        }
+    }
+
+    pub fn is_passable(&self, position: Position) -> bool {
+        self.get(position).is_passable()
+    }
+
+    /// Whether something standing on `from` may step onto the neighbouring
+    /// cell `to`. Diagonal steps may not squeeze between two blocked corners.
+    pub fn can_step(&self, from: Position, to: Position) -> bool {
+        if !self.is_passable(to) {
+            return false;
+        }
+
+        if from.x != to.x && from.y != to.y {
+            return self.is_passable(Position::new(to.x, from.y))
+                || self.is_passable(Position::new(from.x, to.y));
+        }
+
+        true
+    }
+
+    /// The passable neighbours reachable from `position` in one step.
+    pub fn neighbours(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        position
+            .neighbours(self.width, self.height)
+            .filter(move |neighbour| self.can_step(position, *neighbour))
+    }
+
+    /// Every passable cell, row by row.
+    pub fn passable_cells(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..=self.height)
+            .flat_map(move |y| (0..=self.width).map(move |x| Position::new(x, y)))
+            .filter(|position| self.is_passable(*position))
+    }
+
+    /// A random passable cell, `None` if the world has none.
+    pub fn random_passable<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Position> {
+        //rejection sampling is fast on mostly open maps, the scan covers the rest.
+        for _ in 0..32 {
+            let position = Position::new(
+                rng.random_range(1..self.width.max(2)),
+                rng.random_range(1..self.height.max(2)),
+            );
+
+            if self.is_passable(position) {
+                return Some(position);
+            }
+        }
+
+        let cells: Vec<Position> = self.passable_cells().collect();
+        if cells.is_empty() {
+            return None;
+        }
+
+        Some(cells[rng.random_range(0..cells.len())])
+    }
+
+    /// The neighbour that gets closest to `target`, or `from` if none does.
+    /// Ties are broken by the straight-line distance so paths stay natural.
+    pub fn step_towards(&self, from: Position, target: Position) -> Position {
+        let score = |position: Position| {
+            let dx = i32::from(position.x) - i32::from(target.x);
+            let dy = i32::from(position.y) - i32::from(target.y);
+            (position.distance(target), dx * dx + dy * dy)
+        };
+
+        self.neighbours(from)
+            .min_by_key(|neighbour| score(*neighbour))
+            .filter(|neighbour| score(*neighbour) < score(from))
+            .unwrap_or(from)
+    }
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(std::io::Error),
+    Empty,
+    TooSmall,
+    /// More rows or columns than a world can have.
+    TooLarge,
+    Ragged {
+        line: usize,
+    },
+    UnknownTile {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::Io(error) => write!(f, "could not read map: {}", error),
+            MapError::Empty => write!(f, "map is empty"),
+            MapError::TooSmall => write!(f, "map needs at least 3 rows and 3 columns"),
+            MapError::TooLarge => write!(
+                f,
+                "map has more than {} rows or columns",
+                u32::from(u16::MAX) + 1
+            ),
+            MapError::Ragged { line } => {
+                write!(f, "line {} is not as long as the first line", line)
+            }
+            MapError::UnknownTile {
+                line,
+                column,
+                found,
+            } => {
+                write!(
+                    f,
+                    "unknown tile '{}' at line {}, column {}",
+                    found, line, column
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<std::io::Error> for MapError {
+    fn from(error: std::io::Error) -> Self {
+        MapError::Io(error)
+    }
+}
+
+/// A scenario read from an ASCII map file.
+///
+/// Every line is one row of the world, every character one cell:
+///
+/// | char        | meaning                               |
+/// |-------------|---------------------------------------|
+/// | `.` / space | open ground                           |
+/// | `#`         | wall                                  |
+/// | `~`         | water (impassable)                    |
+/// | `:`         | rough terrain, costs 2 ticks to enter |
+/// | `2`..`9`    | rough terrain with that cost          |
+/// | `C`         | a colony (several make a tournament)  |
+/// | `F`         | a fixed food source                   |
+///
+/// The outermost rows and columns are always turned into walls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    pub terrain: Terrain,
+    /// Colony positions, top to bottom and left to right.
+    pub colonies: Vec<Position>,
+    pub food_sources: Vec<Position>,
+}
+
+impl Map {
+    /// An empty rectangle with no colony or food placed.
+    pub fn open(width: u16, height: u16) -> Self {
+        Map {
+            terrain: Terrain::open(width, height),
+            colonies: Vec::new(),
+            food_sources: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        Map::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, MapError> {
+        let rows: Vec<Vec<char>> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().collect())
+            .collect();
+
+        //trailing blank lines are just the end of the file.
+        let rows_used = rows
+            .iter()
+            .rposition(|row| !row.is_empty())
+            .map_or(0, |last| last + 1);
+        let rows = &rows[..rows_used];
+
+        if rows.is_empty() {
+            return Err(MapError::Empty);
+        }
+
+        let columns = rows[0].len();
+        if columns < 3 || rows.len() < 3 {
+            return Err(MapError::TooSmall);
+        }
+
+        //the last row and column have to be addressable as u16 positions.
+        let width = u16::try_from(columns - 1).map_err(|_| MapError::TooLarge)?;
+        let height = u16::try_from(rows.len() - 1).map_err(|_| MapError::TooLarge)?;
+        let mut terrain = Terrain::open(width, height);
+        let mut colonies = Vec::new();
+        let mut food_sources = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != columns {
+                return Err(MapError::Ragged { line: y + 1 });
+            }
+
+            for (x, &found) in row.iter().enumerate() {
+                let position = Position::new(x as u16, y as u16);
+
+                let tile = match found {
+                    '.' | ' ' => Tile::Open,
+                    '#' => Tile::Wall,
+                    '~' => Tile::Water,
+                    ':' => Tile::Rough(2),
+                    '2'..='9' => Tile::Rough(found as u8 - b'0'),
+                    'C' => {
+                        colonies.push(position);
+                        Tile::Open
+                    }
+                    'F' => {
+                        food_sources.push(position);
+                        Tile::Open
+                    }
+                    _ => {
+                        return Err(MapError::UnknownTile {
+                            line: y + 1,
+                            column: x + 1,
+                            found,
+                        })
+                    }
+                };
+
+                terrain.set(position, tile);
+            }
+        }
+
+        //anything sitting on the forced border is dropped.
+        colonies.retain(|position| terrain.is_passable(*position));
+        food_sources.retain(|position| terrain.is_passable(*position));
+
+        Ok(Map {
+            terrain,
+            colonies,
+            food_sources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tiles_and_placements() {
+        let map = Map::parse("#####\n#C:~#\n#F#3#\n#####\n").unwrap();
+
+        assert_eq!((map.terrain.width(), map.terrain.height()), (4, 3));
+        assert_eq!(map.colonies, vec![Position::new(1, 1)]);
+        assert_eq!(map.food_sources, vec![Position::new(1, 2)]);
+        assert_eq!(map.terrain.get(Position::new(2, 1)), Tile::Rough(2));
+        assert_eq!(map.terrain.get(Position::new(3, 1)), Tile::Water);
+        assert_eq!(map.terrain.get(Position::new(3, 2)), Tile::Rough(3));
+        assert!(!map.terrain.is_passable(Position::new(2, 2)));
+    }
+
+    #[test]
+    fn rejects_ragged_rows_and_unknown_tiles() {
+        assert!(matches!(
+            Map::parse("....\n...\n....\n"),
+            Err(MapError::Ragged { line: 2 })
+        ));
+        assert!(matches!(
+            Map::parse("....\n.x..\n....\n"),
+            Err(MapError::UnknownTile {
+                line: 2,
+                column: 2,
+                found: 'x'
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_maps_too_large_for_a_world() {
+        let row = ".".repeat(usize::from(u16::MAX) + 2);
+        let text = format!("{}\n{}\n{}\n", row, row, row);
+
+        assert!(matches!(Map::parse(&text), Err(MapError::TooLarge)));
+    }
+
+    #[test]
+    fn the_largest_map_still_fits() {
+        let row = ".".repeat(usize::from(u16::MAX) + 1);
+        let text = format!("{}\n{}\n{}\n", row, row, row);
+
+        let map = Map::parse(&text).unwrap();
+        assert_eq!(map.terrain.width, u16::MAX);
+    }
+}
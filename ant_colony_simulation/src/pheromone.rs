@@ -0,0 +1,356 @@
+use serde::{Deserialize, Serialize};
+
+use crate::map::Terrain;
+use crate::position::Position;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PheromoneType {
+    /// Laid by searching ants, leads back to the colony.
+    ToHome,
+    /// Laid by ants carrying food, leads to the food.
+    ToFood,
+}
+
+/// A single deposit, or a read-out of one cell of the `PheromoneField`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pheromone {
+    pub position: Position,
+    pub pheromone_type: PheromoneType,
+    pub concentration: f32,
+    pub duration: u32,
+    pub time_elapsed: u32,
+}
+
+impl Pheromone {
+    /// A unit deposit that fades after `duration` ticks.
+    pub fn new(position: Position, pheromone_type: PheromoneType, duration: u32) -> Self {
+        Pheromone {
+            position,
+            pheromone_type,
+            concentration: 1.0,
+            duration,
+            time_elapsed: 0,
+        }
+    }
+}
+
+//below this a cell is considered empty.
+const MIN_CONCENTRATION: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct PheromoneCell {
+    concentration: f32,
+    duration: u32,
+    time_elapsed: u32,
+}
+
+impl PheromoneCell {
+    fn is_empty(&self) -> bool {
+        self.concentration < MIN_CONCENTRATION
+    }
+}
+
+/// Dense per-cell pheromone concentrations, one layer per `PheromoneType`.
+///
+/// Every tick each cell loses a share of its concentration to evaporation and
+/// hands another share to its four direct neighbours. A cell is
+/// wiped once it is older than the duration of its last deposit. Diffused
+/// cells inherit their source's age, so once ants stop depositing the field
+/// is empty after at most the longest `Pheromone::duration`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PheromoneField {
+    width: u16,
+    height: u16,
+    to_home: Vec<PheromoneCell>,
+    to_food: Vec<PheromoneCell>,
+    //reused buffer of `update`, not part of the field's state.
+    #[serde(skip)]
+    scratch: Vec<PheromoneCell>,
+}
+
+impl PartialEq for PheromoneField {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.to_home == other.to_home
+            && self.to_food == other.to_food
+    }
+}
+
+impl PheromoneField {
+    pub fn new(width: u16, height: u16) -> Self {
+        let cells = (usize::from(width) + 1) * (usize::from(height) + 1);
+
+        PheromoneField {
+            width,
+            height,
+            to_home: vec![PheromoneCell::default(); cells],
+            to_food: vec![PheromoneCell::default(); cells],
+            scratch: Vec::with_capacity(cells),
+        }
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        if position.x > self.width || position.y > self.height {
+            return None;
+        }
+
+        Some(usize::from(position.y) * (usize::from(self.width) + 1) + usize::from(position.x))
+    }
+
+    fn layer(&self, pheromone_type: PheromoneType) -> &Vec<PheromoneCell> {
+        match pheromone_type {
+            PheromoneType::ToHome => &self.to_home,
+            PheromoneType::ToFood => &self.to_food,
+        }
+    }
+
+    fn position_of(&self, index: usize) -> Position {
+        let row = usize::from(self.width) + 1;
+        Position::new((index % row) as u16, (index / row) as u16)
+    }
+
+    /// Adds the deposit's concentration to its cell and restarts the cell's clock.
+    pub fn deposit(&mut self, pheromone: Pheromone) {
+        let Some(index) = self.index(pheromone.position) else {
+            return;
+        };
+
+        let cell = match pheromone.pheromone_type {
+            PheromoneType::ToHome => &mut self.to_home[index],
+            PheromoneType::ToFood => &mut self.to_food[index],
+        };
+
+        cell.concentration += pheromone.concentration;
+        cell.duration = pheromone.duration;
+        cell.time_elapsed = pheromone.time_elapsed;
+    }
+
+    /// The concentration of `pheromone_type` at `position`, `0.0` if there is none.
+    pub fn concentration(&self, position: Position, pheromone_type: PheromoneType) -> f32 {
+        self.get(position, pheromone_type)
+            .map_or(0.0, |pheromone| pheromone.concentration)
+    }
+
+    /// Reads one cell back as a `Pheromone`, if anything is left there.
+    pub fn get(&self, position: Position, pheromone_type: PheromoneType) -> Option<Pheromone> {
+        let cell = self.layer(pheromone_type)[self.index(position)?];
+
+        if cell.is_empty() {
+            return None;
+        }
+
+        Some(Pheromone {
+            position,
+            pheromone_type,
+            concentration: cell.concentration,
+            duration: cell.duration,
+            time_elapsed: cell.time_elapsed,
+        })
+    }
+
+    /// All non-empty cells of both layers.
+    pub fn iter(&self) -> impl Iterator<Item = Pheromone> + '_ {
+        [PheromoneType::ToHome, PheromoneType::ToFood]
+            .into_iter()
+            .flat_map(move |pheromone_type| {
+                self.layer(pheromone_type)
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, cell)| !cell.is_empty())
+                    .map(move |(index, cell)| Pheromone {
+                        position: self.position_of(index),
+                        pheromone_type,
+                        concentration: cell.concentration,
+                        duration: cell.duration,
+                        time_elapsed: cell.time_elapsed,
+                    })
+            })
+    }
+
+    /// Number of non-empty cells across both layers.
+    pub fn len(&self) -> usize {
+        self.to_home
+            .iter()
+            .chain(self.to_food.iter())
+            .filter(|cell| !cell.is_empty())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of cells holding a trail of either type.
+    pub fn covered_cells(&self) -> usize {
+        self.to_home
+            .iter()
+            .zip(self.to_food.iter())
+            .filter(|(home, food)| !home.is_empty() || !food.is_empty())
+            .count()
+    }
+
+    /// Ages, diffuses and evaporates both layers by one tick. Both rates are
+    /// fractions of a cell's concentration. Nothing diffuses into impassable
+    /// terrain.
+    pub fn update(&mut self, terrain: &Terrain, evaporation_rate: f32, diffusion_rate: f32) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+
+        for pheromone_type in [PheromoneType::ToHome, PheromoneType::ToFood] {
+            let mut layer = match pheromone_type {
+                PheromoneType::ToHome => std::mem::take(&mut self.to_home),
+                PheromoneType::ToFood => std::mem::take(&mut self.to_food),
+            };
+
+            self.update_layer(
+                terrain,
+                &mut layer,
+                &mut scratch,
+                evaporation_rate,
+                diffusion_rate,
+            );
+
+            match pheromone_type {
+                PheromoneType::ToHome => self.to_home = layer,
+                PheromoneType::ToFood => self.to_food = layer,
+            }
+        }
+
+        self.scratch = scratch;
+    }
+
+    fn update_layer(
+        &self,
+        terrain: &Terrain,
+        layer: &mut [PheromoneCell],
+        next: &mut Vec<PheromoneCell>,
+        evaporation_rate: f32,
+        diffusion_rate: f32,
+    ) {
+        next.clear();
+        next.extend(layer.iter().map(|cell| PheromoneCell {
+            concentration: cell.concentration * (1.0 - diffusion_rate),
+            ..*cell
+        }));
+
+        //hand a share of every cell to its four neighbours. A freshly reached cell inherits
+        //the age of its source so diffusion can never extend a trail's lifetime.
+        for (index, cell) in layer.iter().enumerate() {
+            if cell.is_empty() {
+                continue;
+            }
+
+            let position = self.position_of(index);
+            let targets = [
+                (i32::from(position.x) - 1, i32::from(position.y)),
+                (i32::from(position.x) + 1, i32::from(position.y)),
+                (i32::from(position.x), i32::from(position.y) - 1),
+                (i32::from(position.x), i32::from(position.y) + 1),
+            ];
+            let inside = targets.map(|(x, y)| {
+                let target = Position::new(x as u16, y as u16);
+                if x >= 1
+                    && x < i32::from(self.width)
+                    && y >= 1
+                    && y < i32::from(self.height)
+                    && terrain.is_passable(target)
+                {
+                    self.index(target)
+                } else {
+                    None
+                }
+            });
+            let count = inside.iter().flatten().count();
+
+            if count == 0 {
+                next[index].concentration += cell.concentration * diffusion_rate;
+                continue;
+            }
+
+            let share = cell.concentration * diffusion_rate / count as f32;
+            for target in inside.into_iter().flatten() {
+                let neighbour = &mut next[target];
+                if neighbour.is_empty() {
+                    neighbour.duration = cell.duration;
+                    neighbour.time_elapsed = cell.time_elapsed;
+                }
+                neighbour.concentration += share;
+            }
+        }
+
+        for (cell, updated) in layer.iter_mut().zip(next.iter()) {
+            *cell = *updated;
+            cell.time_elapsed = cell.time_elapsed.saturating_add(1);
+            cell.concentration *= 1.0 - evaporation_rate;
+
+            if cell.time_elapsed >= cell.duration || cell.is_empty() {
+                *cell = PheromoneCell::default();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposits_add_up_in_their_cell() {
+        let mut field = PheromoneField::new(10, 10);
+        let position = Position::new(3, 4);
+
+        field.deposit(Pheromone::new(position, PheromoneType::ToHome, 60));
+        field.deposit(Pheromone::new(position, PheromoneType::ToHome, 60));
+
+        assert_eq!(field.concentration(position, PheromoneType::ToHome), 2.0);
+        assert_eq!(field.concentration(position, PheromoneType::ToFood), 0.0);
+        assert_eq!(field.len(), 1);
+        //off the grid: nothing to add to.
+        field.deposit(Pheromone::new(
+            Position::new(11, 4),
+            PheromoneType::ToHome,
+            60,
+        ));
+        assert_eq!(field.len(), 1);
+    }
+
+    #[test]
+    fn diffusion_keeps_the_age_of_the_source() {
+        let terrain = Terrain::open(10, 10);
+        let mut field = PheromoneField::new(10, 10);
+        field.deposit(Pheromone {
+            duration: 3,
+            ..Pheromone::new(Position::new(5, 5), PheromoneType::ToFood, 120)
+        });
+
+        field.update(&terrain, 0.0, 0.5);
+        let spread = field
+            .get(Position::new(6, 5), PheromoneType::ToFood)
+            .expect("trail diffused to the neighbour");
+        assert_eq!((spread.duration, spread.time_elapsed), (3, 1));
+
+        field.update(&terrain, 0.0, 0.5);
+        field.update(&terrain, 0.0, 0.5);
+        assert!(field.is_empty());
+    }
+
+    #[test]
+    fn nothing_diffuses_into_walls() {
+        let mut terrain = Terrain::open(10, 10);
+        terrain.set(Position::new(6, 5), crate::map::Tile::Wall);
+        let mut field = PheromoneField::new(10, 10);
+        field.deposit(Pheromone::new(
+            Position::new(5, 5),
+            PheromoneType::ToFood,
+            120,
+        ));
+
+        field.update(&terrain, 0.0, 0.5);
+
+        assert_eq!(
+            field.concentration(Position::new(6, 5), PheromoneType::ToFood),
+            0.0
+        );
+        assert!(field.concentration(Position::new(4, 5), PheromoneType::ToFood) > 0.0);
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// A cell in the world grid. The origin is at the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Position {
+    pub fn new(x: u16, y: u16) -> Self {
+        Position { x, y }
+    }
+
+    /// The up to eight cells around this one that lie inside the border of a
+    /// `width` x `height` world, i.e. `1..width` by `1..height`.
+    pub fn neighbours(self, width: u16, height: u16) -> impl Iterator<Item = Position> {
+        (-1i32..=1)
+            .flat_map(|dy| (-1i32..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| dx != 0 || dy != 0)
+            .filter_map(move |(dx, dy)| {
+                let x = i32::from(self.x) + dx;
+                let y = i32::from(self.y) + dy;
+
+                if x >= 1 && x < i32::from(width) && y >= 1 && y < i32::from(height) {
+                    Some(Position::new(x as u16, y as u16))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Chebyshev distance, i.e. the number of eight-way moves between two cells.
+    pub fn distance(self, other: Position) -> u16 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbours_stay_inside_the_border() {
+        let corner: Vec<Position> = Position::new(1, 1).neighbours(5, 4).collect();
+        assert_eq!(
+            corner,
+            vec![
+                Position::new(2, 1),
+                Position::new(1, 2),
+                Position::new(2, 2)
+            ]
+        );
+
+        for y in 0..=4 {
+            for x in 0..=5 {
+                for neighbour in Position::new(x, y).neighbours(5, 4) {
+                    assert!((1..5).contains(&neighbour.x) && (1..4).contains(&neighbour.y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance_counts_diagonal_steps_once() {
+        assert_eq!(Position::new(1, 1).distance(Position::new(4, 3)), 3);
+        assert_eq!(Position::new(4, 3).distance(Position::new(1, 1)), 3);
+    }
+}
@@ -0,0 +1,233 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::ant::{Ant, AntType, DeathCause};
+use crate::map::Terrain;
+use crate::position::Position;
+
+/// How far (in cells) a predator notices ants.
+pub const PREDATOR_SIGHT: u16 = 4;
+/// Predators never spawn closer than this to the colony.
+pub const PREDATOR_SPAWN_DISTANCE: u16 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PredatorMode {
+    Wandering,
+    Attacking,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Predator {
+    pub position: Position,
+    pub mode: PredatorMode,
+    /// How many ants it can bite per tick.
+    pub handle_n_ants: u32,
+    /// Cells moved per tick.
+    pub moves: u8,
+    pub health: u32,
+    pub damage: u32,
+    pub lifespan: u32,
+    /// Ticks it survives without a kill.
+    pub starvation: u32,
+    /// Ticks since its last kill.
+    pub hunger: u32,
+    pub kills: u32,
+    pub is_alive: bool,
+    pub time_elapsed: u32,
+    /// Ticks left before it can move again, e.g. after entering rough terrain.
+    pub delay: u8,
+}
+
+impl Predator {
+    pub fn new(position: Position) -> Self {
+        Predator {
+            position,
+            mode: PredatorMode::Wandering,
+            handle_n_ants: 2,
+            moves: 1,
+            health: 20,
+            damage: 2,
+            lifespan: 500,
+            starvation: 60,
+            hunger: 0,
+            kills: 0,
+            is_alive: true,
+            time_elapsed: 0,
+            delay: 0,
+        }
+    }
+
+    /// Places a predator on a random open cell away from the colony.
+    pub fn spawn<R: Rng + ?Sized>(
+        terrain: &Terrain,
+        colony_position: Position,
+        rng: &mut R,
+    ) -> Option<Self> {
+        let cells: Vec<Position> = terrain.passable_cells().collect();
+        let far: Vec<Position> = cells
+            .iter()
+            .copied()
+            .filter(|cell| cell.distance(colony_position) >= PREDATOR_SPAWN_DISTANCE)
+            .collect();
+
+        //give up on the safe distance in worlds too small to honour it.
+        let choices = if far.is_empty() { &cells } else { &far };
+        if choices.is_empty() {
+            return None;
+        }
+
+        Some(Predator::new(choices[rng.random_range(0..choices.len())]))
+    }
+}
+
+/// Chases the nearest worker in sight (soldiers only if no worker is around),
+/// otherwise roams randomly.
+pub(crate) fn hunt<R: Rng + ?Sized>(
+    terrain: &Terrain,
+    predator: &mut Predator,
+    ants: &VecDeque<Ant>,
+    rng: &mut R,
+) {
+    if predator.delay > 0 {
+        predator.delay -= 1;
+        return;
+    }
+
+    for _ in 0..predator.moves {
+        let prey = ants
+            .iter()
+            .filter(|ant| ant.is_alive)
+            .filter(|ant| predator.position.distance(ant.position) <= PREDATOR_SIGHT)
+            .min_by_key(|ant| {
+                (
+                    ant.ant_type == AntType::Soldier,
+                    predator.position.distance(ant.position),
+                )
+            });
+
+        match prey {
+            Some(ant) => {
+                predator.mode = PredatorMode::Attacking;
+                predator.position = terrain.step_towards(predator.position, ant.position);
+            }
+            None => {
+                predator.mode = PredatorMode::Wandering;
+
+                let neighbours: Vec<Position> = terrain.neighbours(predator.position).collect();
+                if !neighbours.is_empty() {
+                    predator.position = neighbours[rng.random_range(0..neighbours.len())];
+                }
+            }
+        }
+
+        predator.delay = terrain.get(predator.position).movement_cost() - 1;
+        if predator.delay > 0 {
+            break;
+        }
+    }
+}
+
+/// Bites up to `handle_n_ants` ants within reach. Killing one feeds the predator.
+pub(crate) fn bite(predator: &mut Predator, ants: &mut VecDeque<Ant>) {
+    let mut bites = 0;
+
+    for ant in ants.iter_mut() {
+        if bites == predator.handle_n_ants {
+            break;
+        }
+
+        if !ant.is_alive || predator.position.distance(ant.position) > 1 {
+            continue;
+        }
+
+        ant.health = ant.health.saturating_sub(predator.damage);
+        bites += 1;
+
+        if ant.health == 0 {
+            ant.die(DeathCause::Predator);
+            predator.kills += 1;
+            predator.hunger = 0;
+        }
+    }
+}
+
+/// Ages the predator and kills it once it is too old or has gone hungry too long.
+pub(crate) fn age(predator: &mut Predator) {
+    predator.time_elapsed = predator.time_elapsed.saturating_add(1);
+    predator.hunger = predator.hunger.saturating_add(1);
+
+    if predator.time_elapsed >= predator.lifespan || predator.hunger >= predator.starvation {
+        predator.is_alive = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ant::{self, AntMode};
+
+    fn ant(x: u16, y: u16, ant_type: AntType) -> Ant {
+        Ant::new(
+            Position::new(x, y),
+            ant_type,
+            1,
+            100,
+            20,
+            false,
+            AntMode::Wandering,
+        )
+    }
+
+    #[test]
+    fn soldiers_kill_a_predator() {
+        let mut predators = VecDeque::from([Predator::new(Position::new(5, 5))]);
+        let mut soldier = ant(5, 6, AntType::Soldier);
+        let mut worker = ant(6, 5, AntType::Worker);
+
+        let mut strikes = 0;
+        while predators[0].is_alive {
+            ant::strike(&mut worker, &mut predators);
+            ant::strike(&mut soldier, &mut predators);
+            strikes += 1;
+        }
+
+        //20 health at 3 a strike, workers do not fight.
+        assert_eq!(strikes, 7);
+        assert_eq!((soldier.kills, worker.kills), (1, 0));
+    }
+
+    #[test]
+    fn a_predator_kills_a_worker_within_reach() {
+        let mut predator = Predator::new(Position::new(5, 5));
+        predator.hunger = 30;
+        let mut ants = VecDeque::from([ant(5, 6, AntType::Worker), ant(8, 8, AntType::Worker)]);
+
+        bite(&mut predator, &mut ants);
+
+        assert!(!ants[0].is_alive);
+        assert!(ants[1].is_alive);
+        assert_eq!((predator.kills, predator.hunger), (1, 0));
+    }
+
+    #[test]
+    fn a_predator_dies_of_hunger_or_old_age() {
+        let mut hungry = Predator::new(Position::new(5, 5));
+        for _ in 1..hungry.starvation {
+            age(&mut hungry);
+        }
+        assert!(hungry.is_alive);
+        age(&mut hungry);
+        assert!(!hungry.is_alive);
+
+        //well fed, it still dies once its lifespan is up.
+        let mut old = Predator::new(Position::new(5, 5));
+        for _ in 1..old.lifespan {
+            old.hunger = 0;
+            age(&mut old);
+        }
+        assert!(old.is_alive);
+        age(&mut old);
+        assert!(!old.is_alive);
+    }
+}
@@ -0,0 +1,549 @@
+use ant_colony_simulation::{AntMode, AntType, PheromoneType, Position, Tile, World};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, queue, terminal};
+use std::io::{self, Write};
+
+use crate::controls::{self, Controls};
+
+/// Screen columns per world cell, so an emoji stays inside the cell it
+/// stands for.
+pub const CELL_COLUMNS: u16 = 2;
+
+/// Columns between the right border of the world and the HUD panel.
+const HUD_GAP: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    symbol: char,
+    fg: Color,
+    bg: Color,
+    /// Right half of a wide symbol (an emoji) drawn in the cell to the left.
+    continuation: bool,
+}
+
+const BLANK: Cell = Cell {
+    symbol: ' ',
+    fg: Color::Reset,
+    bg: Color::Reset,
+    continuation: false,
+};
+
+/// One screen worth of cells.
+struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    fn new(width: u16, height: u16) -> Self {
+        Frame {
+            width,
+            height,
+            cells: vec![BLANK; usize::from(width) * usize::from(height)],
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(usize::from(y) * usize::from(self.width) + usize::from(x))
+    }
+
+    //later symbols win: a wide symbol partly covered by another one is blanked.
+    fn clear(&mut self, x: u16, y: u16) {
+        let Some(index) = self.index(x, y) else {
+            return;
+        };
+
+        if self.cells[index].continuation && x > 0 {
+            self.cells[index - 1] = BLANK;
+        }
+        if let Some(next) = self.index(x + 1, y) {
+            if self.cells[next].continuation {
+                self.cells[next] = BLANK;
+            }
+        }
+
+        self.cells[index] = BLANK;
+    }
+
+    fn put(&mut self, x: u16, y: u16, symbol: char, fg: Color, bg: Color) {
+        let Some(index) = self.index(x, y) else {
+            return;
+        };
+
+        self.clear(x, y);
+        self.cells[index] = Cell {
+            symbol,
+            fg,
+            bg,
+            continuation: false,
+        };
+    }
+
+    //emojis take two columns. Without room for both halves nothing is drawn.
+    fn put_wide(&mut self, x: u16, y: u16, symbol: char, bg: Color) {
+        let (Some(index), Some(next)) = (self.index(x, y), self.index(x + 1, y)) else {
+            return;
+        };
+
+        self.clear(x, y);
+        self.clear(x + 1, y);
+        self.cells[index] = Cell {
+            symbol,
+            fg: Color::Reset,
+            bg,
+            continuation: false,
+        };
+        self.cells[next] = Cell {
+            continuation: true,
+            bg,
+            ..BLANK
+        };
+    }
+
+    fn text(&mut self, x: u16, y: u16, text: &str, fg: Color) {
+        for (offset, symbol) in text.chars().enumerate() {
+            let Ok(offset) = u16::try_from(offset) else {
+                return;
+            };
+            self.put(x.saturating_add(offset), y, symbol, fg, Color::Reset);
+        }
+    }
+}
+
+//concentration, symbol and colony colour of the trail shown in a cell.
+type Trail = (f32, char, (u8, u8, u8));
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+//darkens a colour, down to a fifth of it for a fraction of 0.
+fn fill((r, g, b): (u8, u8, u8), fraction: f32) -> Color {
+    let strength = 0.2 + 0.8 * fraction.clamp(0.0, 1.0);
+    let scale = |channel: u8| (f32::from(channel) * strength) as u8;
+
+    Color::Rgb {
+        r: scale(r),
+        g: scale(g),
+        b: scale(b),
+    }
+}
+
+//the colony colour, darker the weaker the trail.
+fn shade(color: (u8, u8, u8), concentration: f32) -> Color {
+    fill(color, 1.0 - (-concentration).exp())
+}
+
+//background of a food source, darker the less is left.
+const FOOD_COLOR: (u8, u8, u8) = (40, 140, 40);
+
+//fills both columns of a world cell with a narrow symbol.
+fn put_cell(frame: &mut Frame, position: Position, symbol: char, fg: Color) {
+    let x = position.x.saturating_mul(CELL_COLUMNS);
+
+    for column in x..x.saturating_add(CELL_COLUMNS) {
+        frame.put(column, position.y, symbol, fg, Color::Reset);
+    }
+}
+
+//draws a two column symbol over a world cell.
+fn put_wide_cell(frame: &mut Frame, position: Position, symbol: char, bg: Color) {
+    let x = position.x.saturating_mul(CELL_COLUMNS);
+    frame.put_wide(x, position.y, symbol, bg);
+}
+
+fn draw_world(frame: &mut Frame, world: &World, controls: &Controls) {
+    for y in 0..=world.height {
+        for x in 0..=world.width {
+            let position = Position::new(x, y);
+            let (symbol, fg) = match world.terrain.get(position) {
+                Tile::Open => continue,
+                Tile::Wall => ('#', Color::Grey),
+                Tile::Water => ('~', Color::Blue),
+                Tile::Rough(_) => (':', Color::DarkYellow),
+            };
+
+            put_cell(frame, position, symbol, fg);
+        }
+    }
+
+    //every cell shows the strongest trail on it, whichever colony laid it.
+    if controls.show_pheromones {
+        let row = usize::from(world.width) + 1;
+        let mut strongest: Vec<Option<Trail>> = vec![None; row * (usize::from(world.height) + 1)];
+
+        for colony in &world.colonies {
+            for pheromone in colony.pheromones.iter() {
+                let index =
+                    usize::from(pheromone.position.y) * row + usize::from(pheromone.position.x);
+                let symbol = match pheromone.pheromone_type {
+                    PheromoneType::ToHome => '.',
+                    PheromoneType::ToFood => '-',
+                };
+
+                if strongest[index].is_none_or(|(best, _, _)| pheromone.concentration > best) {
+                    strongest[index] = Some((pheromone.concentration, symbol, colony.color));
+                }
+            }
+        }
+
+        for (index, trail) in strongest.into_iter().enumerate() {
+            if let Some((concentration, symbol, color)) = trail {
+                let position = Position::new((index % row) as u16, (index / row) as u16);
+                put_cell(frame, position, symbol, shade(color, concentration));
+            }
+        }
+    }
+
+    //the fuller a source, the brighter its background. Harvested farms sprout.
+    for food in &world.foods {
+        let symbol = if food.food_size == 0 { '🌱' } else { '🍎' };
+        let bg = fill(FOOD_COLOR, food.fullness());
+        put_wide_cell(frame, food.position, symbol, bg);
+    }
+
+    //ants get the colour of their colony as background, emojis ignore the foreground.
+    for ant in &world.ants {
+        let symbol = match ant.ant_type {
+            AntType::Worker => '🐜',
+            AntType::Soldier => '🪖',
+        };
+        let bg = rgb(world.colonies[ant.colony].color);
+        put_wide_cell(frame, ant.position, symbol, bg);
+    }
+
+    for predator in &world.predators {
+        put_wide_cell(frame, predator.position, '🕷', Color::Reset);
+    }
+
+    for colony in &world.colonies {
+        put_wide_cell(frame, colony.position, '🏠', rgb(colony.color));
+    }
+
+    frame.text(0, world.height + 1, controls::HELP, Color::DarkGrey);
+}
+
+fn draw_hud(frame: &mut Frame, world: &World, controls: &Controls) {
+    let x = world
+        .width
+        .saturating_add(1)
+        .saturating_mul(CELL_COLUMNS)
+        .saturating_add(HUD_GAP);
+    let mut lines: Vec<(String, Color)> = Vec::new();
+
+    let state = if world.game_over {
+        "all colonies collapsed"
+    } else if controls.paused {
+        "paused"
+    } else {
+        "running"
+    };
+    lines.push((format!("Tick {}  {}", world.tick, state), Color::White));
+    lines.push((
+        format!(
+            "{} ms/tick  trails {}",
+            controls.tick.as_millis(),
+            if controls.show_pheromones {
+                "on"
+            } else {
+                "off"
+            }
+        ),
+        Color::Grey,
+    ));
+    lines.push((
+        format!(
+            "Food sources {} ({} units)  Predators {}",
+            world.foods.len(),
+            world.foods.iter().map(|food| food.food_size).sum::<u32>(),
+            world.predators.len()
+        ),
+        Color::Grey,
+    ));
+
+    for colony in &world.colonies {
+        let ants = || world.ants.iter().filter(|ant| ant.colony == colony.id);
+        let soldiers = ants()
+            .filter(|ant| ant.ant_type == AntType::Soldier)
+            .count();
+        let carrying = ants().filter(|ant| ant.mode == AntMode::Returning).count();
+        let color = rgb(colony.color);
+
+        lines.push((String::new(), Color::Reset));
+        lines.push((format!("Colony {}", colony.id), color));
+        lines.push((
+            format!(
+                "  store {}  delivered {}",
+                colony.food_amount, colony.food_delivered
+            ),
+            color,
+        ));
+        lines.push((
+            format!(
+                "  ants {}  soldiers {}  carrying {}",
+                ants().count(),
+                soldiers,
+                carrying
+            ),
+            color,
+        ));
+        lines.push((
+            format!(
+                "  brood {}  trail cells {}",
+                colony.brood.len(),
+                colony.pheromones.covered_cells()
+            ),
+            color,
+        ));
+    }
+
+    if let Some(id) = controls.inspected {
+        lines.push((String::new(), Color::Reset));
+
+        match world.ant(id) {
+            Some(ant) => {
+                lines.push((
+                    format!("Ant {}  colony {}", ant.id, ant.colony),
+                    Color::White,
+                ));
+                lines.push((format!("  {:?} {:?}", ant.ant_type, ant.mode), Color::Grey));
+                lines.push((
+                    format!("  position ({}, {})", ant.position.x, ant.position.y),
+                    Color::Grey,
+                ));
+                lines.push((
+                    format!("  health {}  kills {}", ant.health, ant.kills),
+                    Color::Grey,
+                ));
+                lines.push((
+                    format!("  age {}/{}", ant.time_elapsed, ant.lifespan),
+                    Color::Grey,
+                ));
+                lines.push((
+                    format!("  hunger {}/{}", ant.hunger, ant.starvation),
+                    Color::Grey,
+                ));
+                lines.push((
+                    format!(
+                        "  food in hand {}  trip {}",
+                        ant.food_in_hand, ant.trip_length
+                    ),
+                    Color::Grey,
+                ));
+                lines.push((
+                    format!(
+                        "  last trail {:?}",
+                        ant.explored_pheromones
+                            .map(|pheromone| (pheromone.position.x, pheromone.position.y))
+                    ),
+                    Color::Grey,
+                ));
+            }
+            None => lines.push((format!("Ant {} is dead", id), Color::White)),
+        }
+    }
+
+    //the help line below the world stays readable, whatever does not fit is cut.
+    for (y, (line, color)) in lines.iter().enumerate().take(usize::from(world.height) + 1) {
+        frame.text(x, y as u16, line, *color);
+    }
+}
+
+/// Draws the world into an off-screen frame and only sends the cells that
+/// differ from the previous frame to the terminal, in a single flush.
+pub struct Renderer {
+    previous: Option<Frame>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer { previous: None }
+    }
+
+    pub fn draw<W: Write>(
+        &mut self,
+        out: &mut W,
+        world: &World,
+        controls: &Controls,
+    ) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+
+        let mut frame = Frame::new(width, height);
+        draw_world(&mut frame, world, controls);
+        draw_hud(&mut frame, world, controls);
+
+        //after a resize nothing on screen can be trusted: start from a blank one.
+        let previous = match self.previous.take() {
+            Some(previous) if previous.width == width && previous.height == height => previous,
+            _ => {
+                queue!(out, terminal::Clear(terminal::ClearType::All))?;
+                Frame::new(width, height)
+            }
+        };
+
+        queue!(out, cursor::Hide)?;
+
+        let mut cursor_at = None;
+        let mut colors = None;
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = usize::from(y) * usize::from(width) + usize::from(x);
+                let cell = frame.cells[index];
+
+                if cell == previous.cells[index] || cell.continuation {
+                    continue;
+                }
+
+                if cursor_at != Some((x, y)) {
+                    queue!(out, cursor::MoveTo(x, y))?;
+                }
+                if colors != Some((cell.fg, cell.bg)) {
+                    queue!(
+                        out,
+                        SetForegroundColor(cell.fg),
+                        SetBackgroundColor(cell.bg)
+                    )?;
+                    colors = Some((cell.fg, cell.bg));
+                }
+                queue!(out, Print(cell.symbol))?;
+
+                let wide = frame
+                    .cells
+                    .get(index + 1)
+                    .is_some_and(|next| next.continuation && x + 1 < width);
+                cursor_at = Some((x + if wide { 2 } else { 1 }, y));
+            }
+        }
+
+        queue!(out, ResetColor)?;
+        out.flush()?;
+
+        self.previous = Some(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_colony_simulation::Predator;
+    use std::path::Path;
+    use std::time::Duration;
+
+    //the symbols of a frame, one line per row, without trailing blanks.
+    fn text(frame: &Frame) -> String {
+        let mut text = String::new();
+
+        for row in frame.cells.chunks(usize::from(frame.width)) {
+            let line: String = row
+                .iter()
+                .filter(|cell| !cell.continuation)
+                .map(|cell| cell.symbol)
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+
+        text
+    }
+
+    //compares with `tests/golden/<name>`. `UPDATE_GOLDEN=1 cargo test`
+    //rewrites the files after an intended change of the picture.
+    fn assert_golden(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name);
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).expect("golden file is writable");
+            return;
+        }
+
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("no golden file {:?}, run with UPDATE_GOLDEN=1", path));
+        assert!(
+            actual == expected,
+            "{} differs from the golden file, run with UPDATE_GOLDEN=1 if that is intended:\n{}",
+            name,
+            actual
+        );
+    }
+
+    fn draw(world: &World, controls: &Controls) -> String {
+        let mut frame = Frame::new(
+            (world.width + 1) * CELL_COLUMNS + HUD_GAP + 60,
+            world.height + 2,
+        );
+        draw_world(&mut frame, world, controls);
+        draw_hud(&mut frame, world, controls);
+        text(&frame)
+    }
+
+    fn seeded_world() -> World {
+        let mut world = World::with_seed(40, 15, 5);
+        world.food_spawn_rate = 0.3;
+        world.run(60);
+        world
+    }
+
+    #[test]
+    fn running_world_with_trails() {
+        let world = seeded_world();
+        let controls = Controls::new(Duration::from_millis(500));
+
+        assert_golden("running.txt", &draw(&world, &controls));
+    }
+
+    #[test]
+    fn paused_world_inspecting_an_ant() {
+        let world = seeded_world();
+        let mut controls = Controls::new(Duration::from_millis(500));
+        controls.paused = true;
+        controls.show_pheromones = false;
+        controls.inspected = world.ants.front().map(|ant| ant.id);
+
+        assert_golden("inspecting.txt", &draw(&world, &controls));
+    }
+
+    #[test]
+    fn later_symbols_blank_the_wide_ones_they_cover() {
+        let mut frame = Frame::new(7, 1);
+        frame.put_wide(1, 0, '🍎', Color::Reset);
+        frame.put(2, 0, '#', Color::Grey, Color::Reset);
+        frame.put_wide(3, 0, '🐜', Color::Reset);
+        frame.put_wide(4, 0, '🐜', Color::Reset);
+        //no room for the right half: nothing is drawn.
+        frame.put_wide(6, 0, '🍎', Color::Reset);
+
+        assert_eq!(text(&frame), "  # 🐜\n");
+    }
+
+    #[test]
+    fn neighbouring_cells_keep_their_wide_symbols() {
+        let mut world = World::with_seed(6, 4, 1);
+        world.ants.clear();
+        world.foods.clear();
+        world.predators.clear();
+        world.colonies.clear();
+        for x in [4, 5] {
+            world
+                .predators
+                .push_back(Predator::new(Position::new(x, 2)));
+        }
+
+        let mut controls = Controls::new(Duration::from_millis(500));
+        controls.show_pheromones = false;
+        let mut frame = Frame::new((world.width + 1) * CELL_COLUMNS, world.height + 1);
+        draw_world(&mut frame, &world, &controls);
+
+        let rows: Vec<String> = text(&frame).lines().map(String::from).collect();
+        assert_eq!(rows[0], "##############");
+        assert_eq!(rows[2], "##      🕷🕷##");
+    }
+}
@@ -0,0 +1,397 @@
+//! Scenario files: every knob of a run in one TOML file.
+//!
+//! Keys left out keep their defaults, so an empty file describes the
+//! standard run. Any key can also be overridden with a `key=value`
+//! assignment (`food.max_size=300`, `aco.alpha=1.5`, ...), which is how the
+//! command line changes a scenario without editing it. See
+//! `scenarios/default.toml` for every key with its default.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::aco::AcoParams;
+use crate::colony::LifecycleParams;
+use crate::food::FoodParams;
+use crate::map::{Map, MapError};
+use crate::position::Position;
+use crate::world::World;
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    /// A command line override that is not a `key=value` assignment.
+    Override(String),
+    Map(MapError),
+    /// A value that cannot make a sensible run.
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "could not read scenario: {}", error),
+            ScenarioError::Parse(error) => write!(f, "malformed scenario: {}", error),
+            ScenarioError::Override(message) => write!(f, "bad override: {}", message),
+            ScenarioError::Map(error) => write!(f, "{}", error),
+            ScenarioError::Invalid(message) => write!(f, "invalid scenario: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl From<std::io::Error> for ScenarioError {
+    fn from(error: std::io::Error) -> Self {
+        ScenarioError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ScenarioError {
+    fn from(error: toml::de::Error) -> Self {
+        ScenarioError::Parse(error)
+    }
+}
+
+impl From<MapError> for ScenarioError {
+    fn from(error: MapError) -> Self {
+        ScenarioError::Map(error)
+    }
+}
+
+fn ensure(condition: bool, message: impl FnOnce() -> String) -> Result<(), ScenarioError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(ScenarioError::Invalid(message()))
+    }
+}
+
+//written this way round so NaN fails as well.
+fn ensure_range(name: &str, value: f64, min: f64, max: f64) -> Result<(), ScenarioError> {
+    ensure(value >= min && value <= max, || {
+        format!(
+            "{} must be between {} and {}, got {}",
+            name, min, max, value
+        )
+    })
+}
+
+fn ensure_non_negative(name: &str, value: f32) -> Result<(), ScenarioError> {
+    ensure(value >= 0.0 && value.is_finite(), || {
+        format!("{} must be a number of 0 or more, got {}", name, value)
+    })
+}
+
+fn ensure_positive(name: &str, value: u32) -> Result<(), ScenarioError> {
+    ensure(value > 0, || format!("{} must be greater than 0", name))
+}
+
+//a TOML literal as it would be written in the file (number, boolean, array,
+//inline table...), anything else as a plain string.
+fn parse_value(text: &str) -> Value {
+    format!("value = {}", text)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+fn apply_override(table: &mut Table, assignment: &str) -> Result<(), ScenarioError> {
+    let Some((key, value)) = assignment.split_once('=') else {
+        return Err(ScenarioError::Override(format!(
+            "expected key=value, got '{}'",
+            assignment
+        )));
+    };
+
+    let mut path: Vec<&str> = key.trim().split('.').collect();
+    let last = path.pop().unwrap_or_default();
+
+    let mut table = table;
+    for part in path {
+        table = table
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| ScenarioError::Override(format!("'{}' is not a table", part)))?;
+    }
+
+    table.insert(last.to_string(), parse_value(value.trim()));
+    Ok(())
+}
+
+/// Everything that describes a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// Seed of the run. Without one the OS provides the entropy.
+    pub seed: Option<u64>,
+    /// ASCII map to load, relative to the working directory. Without one the
+    /// world is an open `width` x `height` rectangle.
+    pub map: Option<PathBuf>,
+    pub width: u16,
+    pub height: u16,
+    /// Milliseconds between two ticks in the terminal front end.
+    pub tick_ms: u64,
+    pub food_spawn_rate: f64,
+    pub predator_spawn_rate: f64,
+    pub max_predators: usize,
+    /// Whether soldiers attack ants of rival colonies.
+    pub warfare: bool,
+    /// Colony positions. When given they replace the colonies of the map.
+    pub colonies: Vec<Position>,
+    /// Movement and trail parameters shared by every colony.
+    pub aco: AcoParams,
+    /// Economy and life cycle parameters shared by every colony.
+    pub lifecycle: LifecycleParams,
+    pub food: FoodParams,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            seed: None,
+            map: None,
+            width: 80,
+            height: 20,
+            tick_ms: 500,
+            food_spawn_rate: 0.1,
+            predator_spawn_rate: 0.005,
+            max_predators: 3,
+            warfare: false,
+            colonies: Vec::new(),
+            aco: AcoParams::default(),
+            lifecycle: LifecycleParams::default(),
+            food: FoodParams::default(),
+        }
+    }
+}
+
+impl Scenario {
+    /// Parses a scenario and applies `overrides` (`key=value` assignments) on
+    /// top of it. The result is validated.
+    pub fn parse<S: AsRef<str>>(text: &str, overrides: &[S]) -> Result<Self, ScenarioError> {
+        Scenario::from_table(text.parse()?, overrides)
+    }
+
+    /// A copy of this scenario with `overrides` applied, validated.
+    pub fn with_overrides<S: AsRef<str>>(&self, overrides: &[S]) -> Result<Self, ScenarioError> {
+        let table =
+            Table::try_from(self).map_err(|error| ScenarioError::Invalid(error.to_string()))?;
+
+        Scenario::from_table(table, overrides)
+    }
+
+    fn from_table<S: AsRef<str>>(mut table: Table, overrides: &[S]) -> Result<Self, ScenarioError> {
+        for assignment in overrides {
+            apply_override(&mut table, assignment.as_ref())?;
+        }
+
+        let scenario: Scenario = Value::Table(table).try_into()?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Reads the scenario at `path`, or starts from the defaults without one,
+    /// and applies `overrides`.
+    pub fn load<S: AsRef<str>>(
+        path: Option<&Path>,
+        overrides: &[S],
+    ) -> Result<Self, ScenarioError> {
+        let text = match path {
+            Some(path) => fs::read_to_string(path)?,
+            None => String::new(),
+        };
+
+        Scenario::parse(&text, overrides)
+    }
+
+    /// Checks every value that does not depend on the map.
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        ensure(self.width >= 2 && self.height >= 2, || {
+            format!(
+                "the world must be at least 2x2, got {}x{}",
+                self.width, self.height
+            )
+        })?;
+        ensure(self.tick_ms > 0, || {
+            "tick_ms must be greater than 0".to_string()
+        })?;
+        ensure_range("food_spawn_rate", self.food_spawn_rate, 0.0, 1.0)?;
+        ensure_range("predator_spawn_rate", self.predator_spawn_rate, 0.0, 1.0)?;
+
+        let aco = &self.aco;
+        ensure_non_negative("aco.alpha", aco.alpha)?;
+        ensure_non_negative("aco.beta", aco.beta)?;
+        ensure_range(
+            "aco.evaporation_rate",
+            f64::from(aco.evaporation_rate),
+            0.0,
+            1.0,
+        )?;
+        ensure_range(
+            "aco.diffusion_rate",
+            f64::from(aco.diffusion_rate),
+            0.0,
+            1.0,
+        )?;
+        ensure_non_negative("aco.deposit_amount", aco.deposit_amount)?;
+        ensure_non_negative("aco.base_concentration", aco.base_concentration)?;
+        ensure(aco.base_concentration > 0.0, || {
+            "aco.base_concentration must be greater than 0".to_string()
+        })?;
+        ensure_range(
+            "aco.backtrack_penalty",
+            f64::from(aco.backtrack_penalty),
+            0.0,
+            1.0,
+        )?;
+        ensure_positive("aco.to_home_duration", aco.to_home_duration)?;
+        ensure_positive("aco.to_food_duration", aco.to_food_duration)?;
+
+        let lifecycle = &self.lifecycle;
+        ensure_positive("lifecycle.worker_lifespan", lifecycle.worker_lifespan)?;
+        ensure_positive("lifecycle.soldier_lifespan", lifecycle.soldier_lifespan)?;
+        ensure_positive("lifecycle.starvation", lifecycle.starvation)?;
+        ensure_positive("lifecycle.egg_interval", lifecycle.egg_interval)?;
+        ensure_positive(
+            "lifecycle.larva_meal_interval",
+            lifecycle.larva_meal_interval,
+        )?;
+
+        let food = &self.food;
+        ensure_positive("food.min_lifespan", food.min_lifespan)?;
+        ensure_positive("food.min_size", food.min_size)?;
+        ensure_positive("food.fixed_size", food.fixed_size)?;
+        ensure(food.min_lifespan <= food.max_lifespan, || {
+            "food.min_lifespan must not exceed food.max_lifespan".to_string()
+        })?;
+        ensure(food.min_size <= food.max_size, || {
+            "food.min_size must not exceed food.max_size".to_string()
+        })?;
+        ensure_positive("food.cluster_size", food.cluster_size)?;
+        ensure_positive("food.regrow_interval", food.regrow_interval)?;
+
+        if self.map.is_none() {
+            for (index, colony) in self.colonies.iter().enumerate() {
+                self.ensure_inside(index, *colony, self.width, self.height)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn ensure_inside(
+        &self,
+        index: usize,
+        colony: Position,
+        width: u16,
+        height: u16,
+    ) -> Result<(), ScenarioError> {
+        ensure(
+            colony.x >= 1 && colony.x < width && colony.y >= 1 && colony.y < height,
+            || {
+                format!(
+                    "colony {} at ({}, {}) is outside the {}x{} world",
+                    index, colony.x, colony.y, width, height
+                )
+            },
+        )
+    }
+
+    /// Loads the map if there is one and builds the world the scenario
+    /// describes.
+    pub fn build(&self) -> Result<World, ScenarioError> {
+        let mut map = match &self.map {
+            Some(path) => Map::load(path)?,
+            None => Map::open(self.width, self.height),
+        };
+
+        if !self.colonies.is_empty() {
+            let terrain = &map.terrain;
+            for (index, colony) in self.colonies.iter().enumerate() {
+                self.ensure_inside(index, *colony, terrain.width(), terrain.height())?;
+                ensure(terrain.is_passable(*colony), || {
+                    format!(
+                        "colony {} at ({}, {}) is not on open ground",
+                        index, colony.x, colony.y
+                    )
+                })?;
+            }
+
+            map.colonies = self.colonies.clone();
+        }
+
+        let rng = match self.seed {
+            Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+            None => ChaCha12Rng::from_os_rng(),
+        };
+
+        let mut world = World::from_map_with(map, self.aco, self.lifecycle, self.food, rng);
+        world.food_spawn_rate = self.food_spawn_rate;
+        world.predator_spawn_rate = self.predator_spawn_rate;
+        world.max_predators = self.max_predators;
+        world.colony_warfare = self.warfare;
+
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(result: Result<Scenario, ScenarioError>) -> String {
+        match result {
+            Err(ScenarioError::Invalid(message)) => message,
+            other => panic!("expected an invalid scenario, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn the_default_file_is_the_default_scenario() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios/default.toml");
+        let scenario = Scenario::load(Some(&path), &[] as &[&str]).unwrap();
+
+        assert_eq!(scenario, Scenario::default());
+        assert!(scenario.build().is_ok());
+    }
+
+    #[test]
+    fn rejects_a_colony_outside_the_world() {
+        let text = "width = 20\nheight = 10\ncolonies = [{ x = 25, y = 5 }]\n";
+        let message = invalid(Scenario::parse(text, &[] as &[&str]));
+
+        assert!(message.contains("outside the 20x10 world"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_a_minimum_above_its_maximum() {
+        let message = invalid(Scenario::parse(
+            "",
+            &["food.min_size=120", "food.max_size=80"],
+        ));
+        assert!(message.contains("food.min_size"), "{}", message);
+
+        let message = invalid(Scenario::parse("", &["food.min_lifespan=300"]));
+        assert!(message.contains("food.min_lifespan"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_malformed_overrides() {
+        let unknown = Scenario::parse("", &["aco.speed=3"]);
+        assert!(matches!(unknown, Err(ScenarioError::Parse(_))));
+
+        let unknown = Scenario::parse("", &["colour=red"]);
+        assert!(matches!(unknown, Err(ScenarioError::Parse(_))));
+
+        let malformed = Scenario::parse("", &["food.max_size"]);
+        assert!(matches!(malformed, Err(ScenarioError::Override(_))));
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::ant::{AntMode, AntType, DeathCause};
+use crate::world::World;
+
+/// One colony at the end of one tick. Food delivered and deaths are totals
+/// since the start of the recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub tick: u64,
+    pub colony: usize,
+    pub workers: usize,
+    pub soldiers: usize,
+    pub wandering: usize,
+    pub returning: usize,
+    pub brood: usize,
+    pub food_amount: u32,
+    pub food_delivered: u32,
+    /// Fraction of the passable cells holding one of the colony's trails.
+    pub pheromone_coverage: f64,
+    /// Mean length of the return trips that ended in a delivery.
+    pub average_trip_length: f64,
+    pub deaths_old_age: u32,
+    pub deaths_starvation: u32,
+    pub deaths_predator: u32,
+    pub deaths_combat: u32,
+}
+
+impl Sample {
+    const CSV_HEADER: &'static str = "tick,colony,workers,soldiers,wandering,returning,brood,\
+        food_amount,food_delivered,pheromone_coverage,average_trip_length,\
+        deaths_old_age,deaths_starvation,deaths_predator,deaths_combat";
+
+    fn write_csv_row<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{:.4},{:.2},{},{},{},{}",
+            self.tick,
+            self.colony,
+            self.workers,
+            self.soldiers,
+            self.wandering,
+            self.returning,
+            self.brood,
+            self.food_amount,
+            self.food_delivered,
+            self.pheromone_coverage,
+            self.average_trip_length,
+            self.deaths_old_age,
+            self.deaths_starvation,
+            self.deaths_predator,
+            self.deaths_combat
+        )
+    }
+}
+
+//running totals the samples are built from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Totals {
+    trips: u64,
+    trip_length: u64,
+    old_age: u32,
+    starvation: u32,
+    predator: u32,
+    combat: u32,
+}
+
+/// Collects a `Sample` per colony and tick while attached to a `World`
+/// through `World::record_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatsRecorder {
+    samples: Vec<Sample>,
+    totals: Vec<Totals>,
+}
+
+impl StatsRecorder {
+    pub fn new() -> Self {
+        StatsRecorder::default()
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    fn totals(&mut self, colony: usize) -> &mut Totals {
+        if self.totals.len() <= colony {
+            self.totals.resize(colony + 1, Totals::default());
+        }
+
+        &mut self.totals[colony]
+    }
+
+    pub(crate) fn delivery(&mut self, colony: usize, trip_length: u32) {
+        let totals = self.totals(colony);
+        totals.trips += 1;
+        totals.trip_length += u64::from(trip_length);
+    }
+
+    pub(crate) fn death(&mut self, colony: usize, cause: DeathCause) {
+        let totals = self.totals(colony);
+        match cause {
+            DeathCause::OldAge => totals.old_age += 1,
+            DeathCause::Starvation => totals.starvation += 1,
+            DeathCause::Predator => totals.predator += 1,
+            DeathCause::Combat => totals.combat += 1,
+        }
+    }
+
+    /// Takes one sample per colony of the world as it is now.
+    pub(crate) fn record(&mut self, world: &World) {
+        let passable = world.terrain.passable_cells().count().max(1);
+
+        for colony in &world.colonies {
+            let ants = || world.ants.iter().filter(|ant| ant.colony == colony.id);
+            let count_type = |ant_type| ants().filter(|ant| ant.ant_type == ant_type).count();
+            let count_mode = |mode| {
+                ants()
+                    .filter(|ant| ant.ant_type == AntType::Worker && ant.mode == mode)
+                    .count()
+            };
+
+            let totals = self.totals(colony.id).clone();
+            let average_trip_length = if totals.trips == 0 {
+                0.0
+            } else {
+                totals.trip_length as f64 / totals.trips as f64
+            };
+
+            self.samples.push(Sample {
+                tick: world.tick,
+                colony: colony.id,
+                workers: count_type(AntType::Worker),
+                soldiers: count_type(AntType::Soldier),
+                wandering: count_mode(AntMode::Wandering),
+                returning: count_mode(AntMode::Returning),
+                brood: colony.brood.len(),
+                food_amount: colony.food_amount,
+                food_delivered: colony.food_delivered,
+                pheromone_coverage: colony.pheromones.covered_cells() as f64 / passable as f64,
+                average_trip_length,
+                deaths_old_age: totals.old_age,
+                deaths_starvation: totals.starvation,
+                deaths_predator: totals.predator,
+                deaths_combat: totals.combat,
+            });
+        }
+    }
+
+    /// Writes the samples as CSV, one row per tick and colony.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{}", Sample::CSV_HEADER)?;
+        for sample in &self.samples {
+            sample.write_csv_row(&mut out)?;
+        }
+
+        out.flush()
+    }
+
+    /// Writes the samples as a JSON array.
+    pub fn write_json<W: Write>(&self, mut out: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut out, &self.samples)?;
+        out.flush()
+    }
+
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_json(BufWriter::new(File::create(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aco::AcoParams;
+    use crate::colony::LifecycleParams;
+    use crate::position::Position;
+
+    //two colonies recorded for a few ticks.
+    fn recorded() -> StatsRecorder {
+        let mut world = World::with_seed(40, 20, 2);
+        world.add_colony(
+            Position::new(5, 5),
+            AcoParams::default(),
+            LifecycleParams::default(),
+        );
+        world.record_stats();
+        world.run(6);
+
+        world.stats.expect("stats were recorded")
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_row_per_colony_and_tick() {
+        let stats = recorded();
+        let mut out = Vec::new();
+        stats.write_csv(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], Sample::CSV_HEADER);
+        assert_eq!(lines.len(), 1 + 6 * 2);
+        let columns = Sample::CSV_HEADER.split(',').count();
+        for (index, line) in lines[1..].iter().enumerate() {
+            let fields: Vec<&str> = line.split(',').collect();
+            assert_eq!(fields.len(), columns);
+            assert_eq!(fields[0], (index / 2 + 1).to_string());
+            assert_eq!(fields[1], (index % 2).to_string());
+        }
+    }
+
+    #[test]
+    fn json_parses_back_to_the_samples() {
+        let stats = recorded();
+        let mut out = Vec::new();
+        stats.write_json(&mut out).unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let rows = parsed.as_array().expect("an array of samples");
+        assert_eq!(rows.len(), stats.samples().len());
+        for (row, sample) in rows.iter().zip(stats.samples()) {
+            assert_eq!(row["tick"], sample.tick);
+            assert_eq!(row["colony"], sample.colony);
+            assert_eq!(row["workers"], sample.workers);
+            assert_eq!(row["food_amount"], sample.food_amount);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use ant_colony_simulation::tuning::{self, Evolution, Param, Tuner, TuningError};
+use ant_colony_simulation::Scenario;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use std::error::Error;
+use std::io::stdout;
+
+use crate::{arg_value, arg_values};
+
+/// Usage of the `tune` subcommand.
+pub const USAGE: &str = "usage: ant_colony_simulation tune <grid|random|evolve> \
+    [--param key=min:max[:steps]]... [--ticks n] [--runs n] [--samples n] \
+    [--population n] [--generations n] [--report file[.csv]]";
+
+//the number following `flag`, or `default` without one.
+fn number<T>(flag: &str, default: T) -> Result<T, Box<dyn Error>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + 'static,
+{
+    match arg_value(flag) {
+        Some(value) => Ok(value.parse()?),
+        None => Ok(default),
+    }
+}
+
+/// Runs a parameter search on top of `scenario` and prints the ranked
+/// report, or writes it to `--report` (as CSV when the file ends in `.csv`).
+pub fn run(scenario: Scenario) -> Result<(), Box<dyn Error>> {
+    let strategy = std::env::args().nth(2).unwrap_or_default();
+
+    //without `--param` the usual suspects are searched.
+    let mut params = arg_values("--param")
+        .iter()
+        .map(|text| text.parse())
+        .collect::<Result<Vec<Param>, TuningError>>()?;
+    if params.is_empty() {
+        params = tuning::default_params();
+    }
+
+    let seed = scenario.seed.unwrap_or(0);
+    let tuner = Tuner::new(
+        scenario,
+        params,
+        number("--ticks", 1000)?,
+        number("--runs", 3)?,
+    )?;
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+
+    let report = match strategy.as_str() {
+        "grid" => tuner.grid(),
+        "random" => tuner.random(number("--samples", 20)?, &mut rng),
+        "evolve" => {
+            let defaults = Evolution::default();
+            let evolution = Evolution {
+                population: number("--population", defaults.population)?,
+                generations: number("--generations", defaults.generations)?,
+                ..defaults
+            };
+            tuner.evolve(&evolution, &mut rng)
+        }
+        _ => return Err(USAGE.into()),
+    };
+
+    match arg_value("--report") {
+        Some(path) if path.ends_with(".csv") => report.save_csv(path)?,
+        Some(path) => report.save_text(path)?,
+        None => report.write_text(stdout().lock())?,
+    }
+
+    Ok(())
+}
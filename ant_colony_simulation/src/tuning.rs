@@ -0,0 +1,634 @@
+//! Searching for good parameters: many headless runs of a scenario, each with
+//! a few of its keys changed, scored by how much food the colonies bring
+//! home.
+//!
+//! Parameters are scenario keys (`aco.alpha`, `food_spawn_rate`, ...) with a
+//! range to search. Candidates are tried on a grid, as a random sample, or
+//! evolved with a simple genetic algorithm. Every candidate is run with the
+//! same seeds, so scores compare settings rather than luck.
+
+use rand::Rng;
+use rayon::prelude::*;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::scenario::{Scenario, ScenarioError};
+
+#[derive(Debug)]
+pub enum TuningError {
+    /// A parameter that is not a `key=min:max[:steps]` range.
+    Param(String),
+    Scenario(ScenarioError),
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TuningError::Param(message) => write!(f, "bad parameter: {}", message),
+            TuningError::Scenario(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TuningError {}
+
+impl From<ScenarioError> for TuningError {
+    fn from(error: ScenarioError) -> Self {
+        TuningError::Scenario(error)
+    }
+}
+
+/// Grid points per parameter when the range does not say.
+pub const DEFAULT_STEPS: usize = 5;
+
+/// One scenario key and the range it is searched over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub key: String,
+    pub min: f64,
+    pub max: f64,
+    /// Values tried on a grid, `min` and `max` included.
+    pub steps: usize,
+    /// Whether the key takes whole numbers. Ranges written without a decimal
+    /// point are integer ranges.
+    pub integer: bool,
+}
+
+impl FromStr for Param {
+    type Err = TuningError;
+
+    /// Parses `key=min:max` or `key=min:max:steps`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || TuningError::Param(format!("expected key=min:max[:steps], got '{}'", text));
+
+        let (key, range) = text.split_once('=').ok_or_else(invalid)?;
+        let parts: Vec<&str> = range.split(':').map(str::trim).collect();
+        let (min, max, steps) = match parts[..] {
+            [min, max] => (min, max, None),
+            [min, max, steps] => (min, max, Some(steps)),
+            _ => return Err(invalid()),
+        };
+
+        let number = |part: &str| part.parse::<f64>().ok().filter(|value| value.is_finite());
+        let (Some(min), Some(max)) = (number(min), number(max)) else {
+            return Err(invalid());
+        };
+        let integer = !parts[..2].iter().any(|part| part.contains(['.', 'e', 'E']));
+        let steps = match steps {
+            Some(steps) => steps.parse().map_err(|_| invalid())?,
+            None => DEFAULT_STEPS,
+        };
+
+        if key.trim().is_empty() || min > max || steps == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Param {
+            key: key.trim().to_string(),
+            min,
+            max,
+            steps,
+            integer,
+        })
+    }
+}
+
+impl Param {
+    /// Brings a value back into the range, rounded for integer keys.
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = value.clamp(self.min, self.max);
+        if self.integer {
+            value.round()
+        } else {
+            value
+        }
+    }
+
+    /// The grid points of the range, without repeats.
+    pub fn grid(&self) -> Vec<f64> {
+        let mut values: Vec<f64> = (0..self.steps)
+            .map(|step| {
+                let fraction = if self.steps == 1 {
+                    0.0
+                } else {
+                    step as f64 / (self.steps - 1) as f64
+                };
+                self.clamp(self.min + (self.max - self.min) * fraction)
+            })
+            .collect();
+
+        values.dedup();
+        values
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.clamp(rng.random_range(self.min..=self.max))
+    }
+
+    /// The value for reading: integers as such, fractions to four places.
+    pub fn format(&self, value: f64) -> String {
+        if self.integer {
+            format!("{}", value as i64)
+        } else {
+            format!("{:.4}", value)
+        }
+    }
+
+    //the value as it is written in a scenario file.
+    fn assignment(&self, value: f64) -> String {
+        if self.integer {
+            format!("{}={}", self.key, value as i64)
+        } else {
+            format!("{}={:?}", self.key, value)
+        }
+    }
+}
+
+/// The parameters searched when none are given: trail duration, food spawn
+/// rate, worker lifespan and the two movement weights.
+pub fn default_params() -> Vec<Param> {
+    [
+        "aco.to_food_duration=30:240",
+        "food_spawn_rate=0.02:0.3",
+        "lifecycle.worker_lifespan=100:800",
+        "aco.alpha=0.5:3.0",
+        "aco.beta=0.5:4.0",
+    ]
+    .iter()
+    .map(|text| text.parse().expect("default parameters are well formed"))
+    .collect()
+}
+
+/// One candidate setting and how it did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    /// Generation that produced the candidate, 0 outside of evolution.
+    pub generation: u32,
+    /// One value per parameter, in the order of the tuner's parameters.
+    pub values: Vec<f64>,
+    /// Food delivered per tick averaged over the runs, or why the setting
+    /// could not be run.
+    pub score: Result<f64, String>,
+}
+
+impl Trial {
+    fn rank_key(&self) -> f64 {
+        self.score.as_ref().copied().unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+/// Settings of the genetic search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evolution {
+    pub population: usize,
+    pub generations: u32,
+    /// Best candidates carried over unchanged into the next generation.
+    pub elite: usize,
+    /// Chance of each value of a child to be nudged.
+    pub mutation_rate: f64,
+    /// Largest nudge, as a fraction of the parameter's range.
+    pub mutation_scale: f64,
+}
+
+impl Default for Evolution {
+    fn default() -> Self {
+        Evolution {
+            population: 20,
+            generations: 10,
+            elite: 4,
+            mutation_rate: 0.2,
+            mutation_scale: 0.1,
+        }
+    }
+}
+
+/// Runs and scores candidates of a base scenario.
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    pub base: Scenario,
+    pub params: Vec<Param>,
+    /// Ticks of every run.
+    pub ticks: u64,
+    /// Runs per candidate. Run `n` is seeded with the base seed plus `n`.
+    pub runs: u32,
+}
+
+impl Tuner {
+    /// Checks that every key exists and that both ends of every range make a
+    /// valid scenario.
+    pub fn new(
+        base: Scenario,
+        params: Vec<Param>,
+        ticks: u64,
+        runs: u32,
+    ) -> Result<Self, TuningError> {
+        if ticks == 0 || runs == 0 {
+            return Err(TuningError::Param(
+                "ticks and runs must be greater than 0".to_string(),
+            ));
+        }
+
+        for param in &params {
+            base.with_overrides(&[param.assignment(param.min)])?;
+            base.with_overrides(&[param.assignment(param.max)])?;
+        }
+
+        Ok(Tuner {
+            base,
+            params,
+            ticks,
+            runs,
+        })
+    }
+
+    /// Average food delivered per tick by the candidate over all runs.
+    pub fn evaluate(&self, values: &[f64]) -> Result<f64, ScenarioError> {
+        let overrides: Vec<String> = self
+            .params
+            .iter()
+            .zip(values)
+            .map(|(param, value)| param.assignment(*value))
+            .collect();
+        let scenario = self.base.with_overrides(&overrides)?;
+        let seed = self.base.seed.unwrap_or(0);
+
+        let mut delivered = 0;
+        for run in 0..self.runs {
+            let mut world = Scenario {
+                seed: Some(seed.wrapping_add(u64::from(run))),
+                ..scenario.clone()
+            }
+            .build()?;
+
+            world.run(self.ticks);
+            delivered += world
+                .colonies
+                .iter()
+                .map(|colony| u64::from(colony.food_delivered))
+                .sum::<u64>();
+        }
+
+        Ok(delivered as f64 / (self.ticks * u64::from(self.runs)) as f64)
+    }
+
+    //candidates are independent, so they are run in parallel.
+    fn trials(&self, generation: u32, candidates: Vec<Vec<f64>>) -> Vec<Trial> {
+        candidates
+            .into_par_iter()
+            .map(|values| {
+                let score = self.evaluate(&values).map_err(|error| error.to_string());
+                Trial {
+                    generation,
+                    values,
+                    score,
+                }
+            })
+            .collect()
+    }
+
+    /// Tries every combination of the parameters' grid points.
+    pub fn grid(&self) -> Report {
+        let mut candidates = vec![Vec::new()];
+        for param in &self.params {
+            candidates = candidates
+                .into_iter()
+                .flat_map(|values: Vec<f64>| {
+                    param.grid().into_iter().map(move |value| {
+                        let mut values = values.clone();
+                        values.push(value);
+                        values
+                    })
+                })
+                .collect();
+        }
+
+        self.report(self.trials(0, candidates))
+    }
+
+    /// Tries `samples` candidates drawn uniformly from the ranges.
+    pub fn random<R: Rng + ?Sized>(&self, samples: usize, rng: &mut R) -> Report {
+        let candidates = (0..samples).map(|_| self.sample(rng)).collect();
+        self.report(self.trials(0, candidates))
+    }
+
+    /// Evolves a population from a random start: the elite survives, the rest
+    /// is bred from parents picked by tournament, with uniform crossover and
+    /// random nudges. The report holds every candidate of every generation.
+    pub fn evolve<R: Rng + ?Sized>(&self, evolution: &Evolution, rng: &mut R) -> Report {
+        let population = evolution.population.max(1);
+        let elite = evolution.elite.min(population);
+
+        let start = (0..population).map(|_| self.sample(rng)).collect();
+        let mut current = self.trials(0, start);
+        let mut history = current.clone();
+
+        for generation in 1..evolution.generations {
+            current.sort_by(|a, b| b.rank_key().total_cmp(&a.rank_key()));
+            let parents = &current;
+
+            let children = (elite..population)
+                .map(|_| {
+                    let mother = tournament(parents, rng);
+                    let father = tournament(parents, rng);
+                    self.breed(mother, father, evolution, rng)
+                })
+                .collect();
+
+            let mut next: Vec<Trial> = current[..elite].to_vec();
+            let children = self.trials(generation, children);
+            history.extend(children.iter().cloned());
+            next.extend(children);
+            current = next;
+        }
+
+        self.report(history)
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        self.params.iter().map(|param| param.sample(rng)).collect()
+    }
+
+    fn breed<R: Rng + ?Sized>(
+        &self,
+        mother: &Trial,
+        father: &Trial,
+        evolution: &Evolution,
+        rng: &mut R,
+    ) -> Vec<f64> {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| {
+                let parent = if rng.random::<bool>() { mother } else { father };
+                let mut value = parent.values[index];
+
+                if rng.random::<f64>() < evolution.mutation_rate {
+                    let reach = (param.max - param.min) * evolution.mutation_scale;
+                    value += rng.random_range(-reach..=reach);
+                }
+
+                param.clamp(value)
+            })
+            .collect()
+    }
+
+    fn report(&self, mut trials: Vec<Trial>) -> Report {
+        //stable, so ties keep the order the candidates were generated in.
+        trials.sort_by(|a, b| b.rank_key().total_cmp(&a.rank_key()));
+
+        Report {
+            params: self.params.clone(),
+            ticks: self.ticks,
+            runs: self.runs,
+            trials,
+        }
+    }
+}
+
+//the better of two random candidates.
+fn tournament<'a, R: Rng + ?Sized>(trials: &'a [Trial], rng: &mut R) -> &'a Trial {
+    let a = &trials[rng.random_range(0..trials.len())];
+    let b = &trials[rng.random_range(0..trials.len())];
+
+    if b.rank_key() > a.rank_key() {
+        b
+    } else {
+        a
+    }
+}
+
+/// Trials ranked best first. Settings that could not be run come last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub params: Vec<Param>,
+    pub ticks: u64,
+    pub runs: u32,
+    pub trials: Vec<Trial>,
+}
+
+impl Report {
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials.first().filter(|trial| trial.score.is_ok())
+    }
+
+    /// Writes an aligned table for reading in the terminal.
+    pub fn write_text<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} candidates, {} runs of {} ticks each, scored by food delivered per tick",
+            self.trials.len(),
+            self.runs,
+            self.ticks
+        )?;
+
+        let widths: Vec<usize> = self
+            .params
+            .iter()
+            .map(|param| param.key.len().max(8))
+            .collect();
+
+        write!(out, "{:>4}  {:>8}  {:>3}", "rank", "score", "gen")?;
+        for (param, width) in self.params.iter().zip(&widths) {
+            write!(out, "  {:>width$}", param.key, width = width)?;
+        }
+        writeln!(out)?;
+
+        for (rank, trial) in self.trials.iter().enumerate() {
+            let score = match &trial.score {
+                Ok(score) => format!("{:.4}", score),
+                Err(_) => "invalid".to_string(),
+            };
+
+            write!(
+                out,
+                "{:>4}  {:>8}  {:>3}",
+                rank + 1,
+                score,
+                trial.generation
+            )?;
+            for ((param, value), width) in self.params.iter().zip(&trial.values).zip(&widths) {
+                write!(out, "  {:>width$}", param.format(*value), width = width)?;
+            }
+            if let Err(error) = &trial.score {
+                write!(out, "  {}", error)?;
+            }
+            writeln!(out)?;
+        }
+
+        out.flush()
+    }
+
+    /// Writes one row per trial. Invalid settings have an empty score and
+    /// the reason in the last column.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let keys: Vec<&str> = self.params.iter().map(|param| param.key.as_str()).collect();
+        writeln!(out, "rank,score,generation,{},error", keys.join(","))?;
+
+        for (rank, trial) in self.trials.iter().enumerate() {
+            let (score, error) = match &trial.score {
+                Ok(score) => (format!("{:.6}", score), String::new()),
+                Err(error) => (String::new(), format!("\"{}\"", error.replace('"', "\"\""))),
+            };
+            let values: Vec<String> = self
+                .params
+                .iter()
+                .zip(&trial.values)
+                .map(|(param, value)| param.format(*value))
+                .collect();
+
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                rank + 1,
+                score,
+                trial.generation,
+                values.join(","),
+                error
+            )?;
+        }
+
+        out.flush()
+    }
+
+    pub fn save_text(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_text(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_csv(BufWriter::new(File::create(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    fn tuner(params: &[&str]) -> Tuner {
+        let base = Scenario {
+            seed: Some(3),
+            width: 30,
+            height: 12,
+            ..Scenario::default()
+        };
+        let params = params.iter().map(|text| text.parse().unwrap()).collect();
+        Tuner::new(base, params, 40, 1).unwrap()
+    }
+
+    fn trial(score: Result<f64, &str>, value: f64) -> Trial {
+        Trial {
+            generation: 0,
+            values: vec![value],
+            score: score.map_err(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_ranges_and_tells_integers_from_fractions() {
+        let param: Param = "aco.alpha = 0.5:3.0:6".parse().unwrap();
+        assert_eq!(
+            param,
+            Param {
+                key: "aco.alpha".to_string(),
+                min: 0.5,
+                max: 3.0,
+                steps: 6,
+                integer: false,
+            }
+        );
+
+        let param: Param = "lifecycle.worker_lifespan=100:800".parse().unwrap();
+        assert!(param.integer);
+        assert_eq!(param.steps, DEFAULT_STEPS);
+    }
+
+    #[test]
+    fn rejects_malformed_ranges() {
+        for text in [
+            "aco.alpha",
+            "aco.alpha=1",
+            "aco.alpha=1:2:3:4",
+            "aco.alpha=a:2",
+            "aco.alpha=1:inf",
+            "aco.alpha=NaN:2",
+            "aco.alpha=3:2",
+            "aco.alpha=1:2:0",
+            "aco.alpha=1:2:x",
+            " =1:2",
+        ] {
+            assert!(
+                matches!(text.parse::<Param>(), Err(TuningError::Param(_))),
+                "{} was accepted",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn grids_cover_every_combination() {
+        let fraction: Param = "food_spawn_rate=0.0:1.0:5".parse().unwrap();
+        assert_eq!(fraction.grid(), [0.0, 0.25, 0.5, 0.75, 1.0]);
+
+        //integer points that round alike are tried once.
+        let integer: Param = "lifecycle.initial_workers=1:3:5".parse().unwrap();
+        assert_eq!(integer.grid(), [1.0, 2.0, 3.0]);
+
+        let report = tuner(&["food_spawn_rate=0.1:0.3:3", "lifecycle.initial_workers=5:6"]).grid();
+        assert_eq!(report.trials.len(), 3 * 2);
+    }
+
+    #[test]
+    fn reports_rank_the_best_first_and_failures_last() {
+        let tuner = tuner(&["food_spawn_rate=0.1:0.3"]);
+        let report = tuner.report(vec![
+            trial(Ok(1.0), 0.1),
+            trial(Err("no colony"), 0.2),
+            trial(Ok(3.0), 0.3),
+            trial(Ok(3.0), 0.4),
+        ]);
+
+        let order: Vec<f64> = report.trials.iter().map(|trial| trial.values[0]).collect();
+        assert_eq!(order, [0.3, 0.4, 0.1, 0.2]);
+        assert_eq!(report.best().map(|trial| trial.values[0]), Some(0.3));
+
+        let failed = tuner.report(vec![trial(Err("no colony"), 0.2)]);
+        assert!(failed.best().is_none());
+    }
+
+    #[test]
+    fn seeded_evolution_is_repeatable_and_keeps_the_best() {
+        let tuner = tuner(&["food_spawn_rate=0.02:0.5", "aco.beta=0.5:4.0"]);
+        let evolution = Evolution {
+            population: 6,
+            generations: 3,
+            elite: 2,
+            ..Evolution::default()
+        };
+
+        let first = tuner.evolve(&evolution, &mut ChaCha12Rng::seed_from_u64(11));
+        let second = tuner.evolve(&evolution, &mut ChaCha12Rng::seed_from_u64(11));
+        assert_eq!(first, second);
+        assert_eq!(first.trials.len(), 6 + 2 * 4);
+
+        //the best of the random start is never lost along the way.
+        let best_of = |generation: u32| {
+            first
+                .trials
+                .iter()
+                .filter(|trial| trial.generation == generation)
+                .map(Trial::rank_key)
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+        let best = first.best().map(Trial::rank_key).unwrap();
+        assert!((0..3).all(|generation| best >= best_of(generation)));
+        assert!(first
+            .trials
+            .windows(2)
+            .all(|pair| pair[0].rank_key() >= pair[1].rank_key()));
+    }
+}
@@ -0,0 +1,683 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::aco::AcoParams;
+use crate::ant::{self, Ant, AntMode, AntType, DeathCause, Decision, Surroundings};
+use crate::colony::{Colony, LifecycleParams};
+use crate::food::{self, Food, FoodParams};
+use crate::map::{Map, Terrain, Tile};
+use crate::position::Position;
+use crate::predator::{self, Predator};
+use crate::stats::StatsRecorder;
+
+/// The whole simulation state. Every random draw goes through `rng`, so two
+/// worlds built from the same seed evolve identically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct World {
+    pub height: u16,
+    pub width: u16,
+    pub game_over: bool,
+    pub terrain: Terrain,
+    pub colonies: Vec<Colony>,
+    pub ants: VecDeque<Ant>,
+    pub foods: VecDeque<Food>,
+    pub predators: VecDeque<Predator>,
+    pub food_spawn_rate: f64,
+    /// Size and lifespan ranges of spawned food.
+    pub food: FoodParams,
+    pub predator_spawn_rate: f64,
+    pub max_predators: usize,
+    /// Whether soldiers attack ants of rival colonies.
+    pub colony_warfare: bool,
+    pub tick: u64,
+    /// Per-tick metrics, collected once `record_stats` was called.
+    pub stats: Option<StatsRecorder>,
+    next_ant_id: u64,
+    rng: ChaCha12Rng,
+}
+
+/// A change made to a running world from outside the simulation, e.g. by a
+/// user clicking on the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Edit {
+    /// Drops a food source of random size and lifespan.
+    Food(Position),
+    /// Raises a wall.
+    Wall(Position),
+}
+
+fn update_ants(ants: &mut VecDeque<Ant>) {
+    ants.retain(|ant| ant.is_alive);
+}
+
+fn new_ant(id: u64, colony: &Colony, ant_type: AntType) -> Ant {
+    Ant {
+        id,
+        colony: colony.id,
+        ..Ant::new(
+            colony.position,
+            ant_type,
+            1,
+            colony.lifecycle.lifespan(ant_type),
+            colony.lifecycle.starvation,
+            false,
+            AntMode::Wandering,
+        )
+    }
+}
+
+fn update_predators(predators: &mut VecDeque<Predator>) {
+    for predator in predators.iter_mut() {
+        predator::age(predator);
+    }
+
+    predators.retain(|predator| predator.is_alive);
+}
+
+/// Soldiers a colony keeps around for every predator on the map.
+pub const SOLDIERS_PER_PREDATOR: usize = 2;
+/// Soldiers a colony keeps around when colonies are at war.
+pub const SOLDIERS_AT_WAR: usize = 2;
+
+//ants decided by one rayon job. Small worlds stay on a single thread.
+const DECISIONS_PER_JOB: usize = 256;
+
+impl World {
+    /// Builds an open world with a single colony on a random cell. Its
+    /// randomness is entirely driven by `rng`.
+    pub fn new(width: u16, height: u16, rng: ChaCha12Rng) -> Self {
+        World::from_map(Map::open(width, height), rng)
+    }
+
+    /// Builds a world from a loaded map, with one colony per colony marker.
+    /// Without any marker a single colony is placed on a random open cell.
+    pub fn from_map(map: Map, rng: ChaCha12Rng) -> Self {
+        World::from_map_with(
+            map,
+            AcoParams::default(),
+            LifecycleParams::default(),
+            FoodParams::default(),
+            rng,
+        )
+    }
+
+    /// Like `from_map`, but every colony gets `aco` and `lifecycle` and food
+    /// is drawn from `food`.
+    pub fn from_map_with(
+        map: Map,
+        aco: AcoParams,
+        lifecycle: LifecycleParams,
+        food: FoodParams,
+        rng: ChaCha12Rng,
+    ) -> Self {
+        let terrain = map.terrain;
+        let (width, height) = (terrain.width(), terrain.height());
+
+        let mut world = World {
+            height,
+            width,
+            game_over: false,
+            terrain,
+            colonies: Vec::new(),
+            ants: VecDeque::new(),
+            foods: map
+                .food_sources
+                .into_iter()
+                .map(|position| Food::fixed(position, &food))
+                .collect(),
+            predators: VecDeque::new(),
+            food_spawn_rate: 0.1,
+            food,
+            predator_spawn_rate: 0.005,
+            max_predators: 3,
+            colony_warfare: false,
+            tick: 0,
+            stats: None,
+            next_ant_id: 0,
+            rng,
+        };
+
+        if map.colonies.is_empty() {
+            let position = world
+                .terrain
+                .random_passable(&mut world.rng)
+                .unwrap_or(Position::new(width / 2, height / 2));
+            world.add_colony(position, aco, lifecycle);
+        }
+
+        for position in map.colonies {
+            world.add_colony(position, aco, lifecycle);
+        }
+
+        for _ in 0..world.food.farms {
+            if let Some(position) = world.terrain.random_passable(&mut world.rng) {
+                world.foods.push_back(Food::fixed(position, &world.food));
+            }
+        }
+
+        world
+    }
+
+    /// Convenience constructor for reproducible runs.
+    pub fn with_seed(width: u16, height: u16, seed: u64) -> Self {
+        World::new(width, height, ChaCha12Rng::seed_from_u64(seed))
+    }
+
+    /// Founds another colony with its own parameters and initial workers.
+    /// Returns its id.
+    pub fn add_colony(
+        &mut self,
+        position: Position,
+        aco: AcoParams,
+        lifecycle: LifecycleParams,
+    ) -> usize {
+        let id = self.colonies.len();
+        let colony = Colony::new(id, position, self.width, self.height, aco, lifecycle);
+
+        for _ in 0..lifecycle.initial_workers {
+            let ant = new_ant(self.next_ant_id, &colony, AntType::Worker);
+            self.next_ant_id += 1;
+            self.ants.push_back(ant);
+        }
+
+        self.colonies.push(colony);
+        id
+    }
+
+    /// Starts collecting a `stats::Sample` per colony at the end of every tick.
+    pub fn record_stats(&mut self) {
+        if self.stats.is_none() {
+            self.stats = Some(StatsRecorder::new());
+        }
+    }
+
+    /// The living ant with the given id.
+    pub fn ant(&self, id: u64) -> Option<&Ant> {
+        self.ants.iter().find(|ant| ant.id == id)
+    }
+
+    /// Applies an edit if it fits the cell: food and walls go on passable
+    /// cells only, and walls neither on a colony, on food nor on an ant or a
+    /// predator. Returns whether the world changed.
+    pub fn apply(&mut self, edit: Edit) -> bool {
+        match edit {
+            Edit::Food(position) => {
+                if !self.terrain.is_passable(position) {
+                    return false;
+                }
+
+                let food = Food::new(position, &self.food, &mut self.rng);
+                self.foods.push_back(food);
+            }
+            Edit::Wall(position) => {
+                let occupied = self
+                    .colonies
+                    .iter()
+                    .any(|colony| colony.position == position)
+                    || self.foods.iter().any(|food| food.position == position)
+                    || self.ants.iter().any(|ant| ant.position == position)
+                    || self
+                        .predators
+                        .iter()
+                        .any(|predator| predator.position == position);
+                if occupied || !self.terrain.is_passable(position) {
+                    return false;
+                }
+
+                self.terrain.set(position, Tile::Wall);
+            }
+        }
+
+        true
+    }
+
+    //drops one spawn of food, a single source or a whole patch.
+    fn spawn_food(&mut self) {
+        let positions =
+            food::spawn_positions(&self.terrain, &self.foods, &self.food, &mut self.rng);
+
+        for position in positions {
+            let food = Food::new(position, &self.food, &mut self.rng);
+            self.foods.push_back(food);
+        }
+    }
+
+    /// Living ants of the given colony.
+    pub fn population(&self, colony: usize) -> usize {
+        self.ants.iter().filter(|ant| ant.colony == colony).count()
+    }
+
+    /// Advances the simulation by one tick. Never touches the terminal.
+    pub fn step(&mut self) {
+        if self.game_over {
+            return;
+        }
+
+        //the queens lay, brood grows. New adults become soldiers while the guard
+        //is too small for the threats around, workers otherwise.
+        for index in 0..self.colonies.len() {
+            let adults = self.population(index);
+            let matured = self.colonies[index].tend_brood(adults);
+
+            for _ in 0..matured {
+                let soldiers = self
+                    .ants
+                    .iter()
+                    .filter(|ant| ant.colony == index && ant.ant_type == AntType::Soldier)
+                    .count();
+                let mut needed = self.predators.len() * SOLDIERS_PER_PREDATOR;
+                if self.colony_warfare && self.colonies.len() > 1 {
+                    needed = needed.max(SOLDIERS_AT_WAR);
+                }
+
+                let ant_type = if soldiers < needed {
+                    AntType::Soldier
+                } else {
+                    AntType::Worker
+                };
+
+                let ant = new_ant(self.next_ant_id, &self.colonies[index], ant_type);
+                self.next_ant_id += 1;
+                self.ants.push_back(ant);
+            }
+        }
+
+        //move ants towards food or back to their colony, soldiers towards threats.
+        //Every ant decides against the world as it was at the start of the tick,
+        //in parallel, then the moves are carried out one ant after the other.
+        let ant_positions: Vec<(usize, Position)> = self
+            .ants
+            .iter()
+            .map(|ant| (ant.colony, ant.position))
+            .collect();
+
+        //each ant rolls its own stream of the tick's seed, so its decision does
+        //not depend on how the work was split between threads.
+        let tick_seed: <ChaCha12Rng as SeedableRng>::Seed = self.rng.random();
+
+        let decisions: Vec<Option<Decision>> = self
+            .ants
+            .par_iter()
+            .with_min_len(DECISIONS_PER_JOB)
+            .map(|ant| {
+                //still crossing rough terrain.
+                if ant.delay > 0 {
+                    return None;
+                }
+
+                let colony = &self.colonies[ant.colony];
+                let surroundings = Surroundings {
+                    terrain: &self.terrain,
+                    colony: colony.id,
+                    colony_position: colony.position,
+                    ant_positions: &ant_positions,
+                    warfare: self.colony_warfare,
+                    foods: &self.foods,
+                    predators: &self.predators,
+                    pheromones: &colony.pheromones,
+                    params: &colony.aco,
+                };
+
+                let mut rng = ChaCha12Rng::from_seed(tick_seed);
+                rng.set_stream(ant.id);
+
+                Some(ant::decide(ant, &surroundings, &mut rng))
+            })
+            .collect();
+
+        for (ant, decision) in self.ants.iter_mut().zip(decisions) {
+            let Some(decision) = decision else {
+                ant.delay -= 1;
+                continue;
+            };
+
+            let colony = &mut self.colonies[ant.colony];
+            let trip_length = ant.trip_length;
+
+            if ant::act(
+                ant,
+                decision,
+                &mut colony.pheromones,
+                colony.position,
+                &colony.aco,
+            ) {
+                colony.deposit(1);
+
+                if let Some(stats) = &mut self.stats {
+                    stats.delivery(ant.colony, trip_length);
+                }
+            }
+
+            ant.delay = self.terrain.get(ant.position).movement_cost() - 1;
+        }
+
+        for ant in &mut self.ants {
+            if ant.ant_type == AntType::Worker {
+                ant::check_ant_found_food(ant, &mut self.foods);
+            }
+        }
+
+        for predator in &mut self.predators {
+            predator::hunt(&self.terrain, predator, &self.ants, &mut self.rng);
+        }
+
+        //soldiers strike first, then surviving predators bite back.
+        let mut free_soldiers = Vec::new();
+        for (index, ant) in self.ants.iter_mut().enumerate() {
+            if !ant::strike(ant, &mut self.predators) && ant.ant_type == AntType::Soldier {
+                free_soldiers.push(index);
+            }
+        }
+
+        if self.colony_warfare {
+            self.fight_rivals(&free_soldiers);
+        }
+
+        for predator in &mut self.predators {
+            if predator.is_alive {
+                predator::bite(predator, &mut self.ants);
+            }
+        }
+
+        update_predators(&mut self.predators);
+
+        if self.predators.len() < self.max_predators
+            && self.rng.random::<f64>() < self.predator_spawn_rate
+        {
+            let home = self.colonies[self.rng.random_range(0..self.colonies.len())].position;
+
+            if let Some(predator) = Predator::spawn(&self.terrain, home, &mut self.rng) {
+                self.predators.push_back(predator);
+            }
+        }
+
+        for ant in &mut self.ants {
+            if ant.is_alive {
+                ant::metabolise(ant, &mut self.colonies[ant.colony]);
+            }
+        }
+
+        if let Some(stats) = &mut self.stats {
+            for ant in &self.ants {
+                if let Some(cause) = ant.cause_of_death {
+                    stats.death(ant.colony, cause);
+                }
+            }
+        }
+
+        update_ants(&mut self.ants);
+
+        for colony in &mut self.colonies {
+            colony.pheromones.update(
+                &self.terrain,
+                colony.aco.evaporation_rate,
+                colony.aco.diffusion_rate,
+            );
+        }
+
+        let ants = &self.ants;
+        if self.colonies.iter().all(|colony| {
+            colony.has_collapsed(ants.iter().filter(|ant| ant.colony == colony.id).count())
+        }) {
+            self.game_over = true;
+        }
+
+        if self.rng.random::<f64>() < self.food_spawn_rate {
+            self.spawn_food();
+        }
+
+        //every season opens with a burst of food.
+        let season_length = u64::from(self.food.season_length);
+        if season_length > 0 && self.tick.is_multiple_of(season_length) {
+            for _ in 0..self.food.season_burst {
+                self.spawn_food();
+            }
+        }
+
+        food::update_foods(&mut self.foods, &self.food);
+
+        self.tick += 1;
+
+        if let Some(mut stats) = self.stats.take() {
+            stats.record(self);
+            self.stats = Some(stats);
+        }
+    }
+
+    //every soldier that had no predator to hit attacks a rival ant within reach.
+    //All hits are collected first so the order of the ants does not matter.
+    fn fight_rivals(&mut self, soldiers: &[usize]) {
+        let mut hits = Vec::new();
+
+        for &index in soldiers {
+            let soldier = &self.ants[index];
+            if !soldier.is_alive {
+                continue;
+            }
+
+            let target = self.ants.iter().position(|other| {
+                other.is_alive
+                    && other.colony != soldier.colony
+                    && other.position.distance(soldier.position) <= 1
+            });
+
+            if let Some(target) = target {
+                hits.push((index, target, soldier.ant_type.damage()));
+            }
+        }
+
+        for (attacker, target, damage) in hits {
+            let victim = &mut self.ants[target];
+            if !victim.is_alive {
+                continue;
+            }
+
+            victim.health = victim.health.saturating_sub(damage);
+            if victim.health == 0 {
+                victim.die(DeathCause::Combat);
+                self.ants[attacker].kills += 1;
+            }
+        }
+    }
+
+    /// Runs `ticks` steps back to back.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colony::{Brood, BroodStage};
+    use crate::pheromone::{Pheromone, PheromoneType};
+
+    #[test]
+    fn the_same_seed_makes_the_same_run() {
+        let mut a = World::with_seed(40, 20, 9);
+        let mut b = World::with_seed(40, 20, 9);
+
+        a.run(200);
+        b.run(200);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_seeds_make_different_runs() {
+        let mut a = World::with_seed(40, 20, 9);
+        let mut b = World::with_seed(40, 20, 10);
+
+        a.run(50);
+        b.run(50);
+
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn steps_headlessly_until_the_game_is_over() {
+        let mut world = World::with_seed(30, 15, 3);
+        let start = world.clone();
+
+        world.step();
+        assert_eq!(world.tick, start.tick + 1);
+        world.run(9);
+        assert_eq!(world.tick, start.tick + 10);
+        let moved = |world: &World| {
+            world
+                .ants
+                .iter()
+                .map(|ant| ant.position)
+                .collect::<Vec<_>>()
+        };
+        assert_ne!(moved(&world), moved(&start));
+
+        //a finished world stays as it is.
+        world.game_over = true;
+        let over = world.clone();
+        world.step();
+        assert_eq!(world, over);
+    }
+
+    #[test]
+    fn grown_brood_joins_as_soldiers_while_predators_are_about() {
+        let mut world = World::with_seed(40, 20, 4);
+        world.ants.clear();
+        world.colonies[0].lifecycle.egg_interval = u32::MAX;
+        let corner = Position::new(
+            if world.colonies[0].position.x < 20 {
+                38
+            } else {
+                1
+            },
+            1,
+        );
+        world.predators = VecDeque::from([Predator::new(corner)]);
+        let larva = Brood {
+            stage: BroodStage::Larva,
+            age: world.colonies[0].lifecycle.larva_ticks,
+            hunger: 0,
+        };
+        world.colonies[0].brood = vec![larva; 3];
+
+        world.step();
+
+        let types: Vec<AntType> = world.ants.iter().map(|ant| ant.ant_type).collect();
+        assert_eq!(types, [AntType::Soldier, AntType::Soldier, AntType::Worker]);
+        assert!(world.colonies[0].brood.is_empty());
+    }
+
+    #[test]
+    fn a_colony_follows_only_its_own_trails() {
+        let mut world = World::with_seed(40, 20, 6);
+        let home = world.colonies[0].position;
+        let rival = Position::new(if home.x < 20 { 35 } else { 4 }, 10);
+        world.add_colony(rival, AcoParams::default(), LifecycleParams::default());
+        world.ants.retain(|ant| ant.colony == 0);
+
+        //a strong trail leading away from the first nest.
+        let trail = |world: &mut World, colony: usize| {
+            for position in world.terrain.neighbours(home).collect::<Vec<_>>() {
+                world.colonies[colony].pheromones.deposit(Pheromone {
+                    concentration: 50.0,
+                    ..Pheromone::new(position, PheromoneType::ToFood, 120)
+                });
+            }
+        };
+        let positions = |mut world: World| {
+            world.run(10);
+            world
+                .ants
+                .iter()
+                .map(|ant| ant.position)
+                .collect::<Vec<_>>()
+        };
+
+        let mut theirs = world.clone();
+        trail(&mut theirs, 1);
+        let mut ours = world.clone();
+        trail(&mut ours, 0);
+
+        assert_eq!(positions(theirs), positions(world.clone()));
+        assert_ne!(positions(ours), positions(world));
+    }
+
+    #[test]
+    fn a_rival_soldier_kills_an_ant_within_reach() {
+        let mut world = World::with_seed(40, 20, 6);
+        world.add_colony(
+            Position::new(5, 5),
+            AcoParams::default(),
+            LifecycleParams::default(),
+        );
+        let mut soldier = new_ant(0, &world.colonies[0], AntType::Soldier);
+        soldier.position = Position::new(10, 10);
+        let mut worker = new_ant(1, &world.colonies[1], AntType::Worker);
+        worker.position = Position::new(11, 11);
+        world.ants = VecDeque::from([soldier, worker]);
+
+        world.fight_rivals(&[0]);
+        update_ants(&mut world.ants);
+
+        assert_eq!(world.ants.len(), 1);
+        assert_eq!(world.ants[0].kills, 1);
+        assert_eq!(world.ants[0].colony, 0);
+    }
+
+    #[test]
+    fn walls_are_not_raised_on_anything_standing_there() {
+        let mut world = World::with_seed(20, 10, 1);
+        let colony = world.colonies[0].position;
+        assert!(!world.apply(Edit::Wall(colony)));
+
+        world.ants[0].position = Position::new(3, 3);
+        assert!(!world.apply(Edit::Wall(Position::new(3, 3))));
+        assert!(world.terrain.is_passable(Position::new(3, 3)));
+    }
+
+    #[test]
+    fn the_thread_count_does_not_change_a_run() {
+        let mut world = World::with_seed(120, 60, 7);
+        let lifecycle = LifecycleParams {
+            initial_workers: 600,
+            ..LifecycleParams::default()
+        };
+        for position in [Position::new(20, 20), Position::new(100, 40)] {
+            world.add_colony(position, AcoParams::default(), lifecycle);
+        }
+        assert!(world.ants.len() > 4 * DECISIONS_PER_JOB);
+
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let mut world = world.clone();
+            pool.install(|| world.run(30));
+            world
+        };
+
+        assert_eq!(run(1), run(4));
+    }
+
+    #[test]
+    fn every_season_opens_with_a_burst_of_food() {
+        let mut world = World::with_seed(40, 20, 2);
+        world.foods.clear();
+        world.food_spawn_rate = 0.0;
+        world.food.season_length = 10;
+        world.food.season_burst = 4;
+
+        world.step();
+        assert_eq!(world.foods.len(), 4);
+        world.run(9);
+        assert_eq!(world.foods.len(), 4);
+        world.step();
+        assert_eq!(world.foods.len(), 8);
+    }
+}
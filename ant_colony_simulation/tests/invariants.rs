@@ -0,0 +1,157 @@
+//! Invariants that must hold over long seeded runs, whatever the seed, the
+//! size of the world or the walls raised in it.
+
+use ant_colony_simulation::{
+    AntMode, Edit, Pheromone, PheromoneField, PheromoneType, Position, Terrain, World,
+};
+use proptest::prelude::*;
+
+fn inside(world: &World, position: Position) -> bool {
+    position.x >= 1
+        && position.x < world.width
+        && position.y >= 1
+        && position.y < world.height
+        && world.terrain.is_passable(position)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn ants_and_predators_stay_on_open_cells(
+        seed in any::<u64>(),
+        width in 4u16..60,
+        height in 4u16..30,
+        walls in prop::collection::vec((any::<u16>(), any::<u16>()), 0..80),
+        warfare in any::<bool>(),
+    ) {
+        let mut world = World::with_seed(width, height, seed);
+        world.colony_warfare = warfare;
+        world.predator_spawn_rate = 0.05;
+
+        //walls go up while the ants are out, one per tick.
+        for tick in 0..300 {
+            if let Some(&(x, y)) = walls.get(tick) {
+                world.apply(Edit::Wall(Position::new(x % width, y % height)));
+            }
+            world.step();
+
+            for ant in &world.ants {
+                prop_assert!(inside(&world, ant.position), "ant {} at {:?}", ant.id, ant.position);
+            }
+            for predator in &world.predators {
+                prop_assert!(inside(&world, predator.position), "predator at {:?}", predator.position);
+            }
+        }
+    }
+
+    #[test]
+    fn trails_never_outlive_their_duration(
+        seed in any::<u64>(),
+        width in 4u16..60,
+        height in 4u16..30,
+    ) {
+        let mut world = World::with_seed(width, height, seed);
+
+        for _ in 0..300 {
+            world.step();
+
+            for colony in &world.colonies {
+                for pheromone in colony.pheromones.iter() {
+                    prop_assert!(
+                        pheromone.time_elapsed < pheromone.duration,
+                        "{:?} outlived its duration",
+                        pheromone
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_trail_is_gone_once_its_duration_is_up(
+        x in 1u16..20,
+        y in 1u16..10,
+        duration in 1u32..200,
+        concentration in 0.1f32..100.0,
+        diffusion_rate in 0.0f32..1.0,
+    ) {
+        let terrain = Terrain::open(20, 10);
+        let mut field = PheromoneField::new(20, 10);
+        field.deposit(Pheromone {
+            concentration,
+            ..Pheromone::new(Position::new(x, y), PheromoneType::ToFood, duration)
+        });
+
+        for _ in 0..duration {
+            field.update(&terrain, 0.0, diffusion_rate);
+        }
+
+        prop_assert!(field.is_empty(), "{} cells left", field.len());
+    }
+
+    #[test]
+    fn every_unit_of_food_taken_is_carried_or_delivered(
+        seed in any::<u64>(),
+        sources in prop::collection::vec((1u16..40, 1u16..20), 1..12),
+    ) {
+        //no spawns, expiry, regrowth or deaths, so units can only move from
+        //the sources into the ants' hands and on to the colony.
+        let mut world = World::with_seed(40, 20, seed);
+        world.food_spawn_rate = 0.0;
+        world.predator_spawn_rate = 0.0;
+        world.food.min_lifespan = u32::MAX;
+        world.food.max_lifespan = u32::MAX;
+        world.food.min_size = 1;
+        world.food.max_size = 5;
+        for &(x, y) in &sources {
+            world.apply(Edit::Food(Position::new(x, y)));
+        }
+        for ant in world.ants.iter_mut() {
+            ant.lifespan = u32::MAX;
+            ant.starvation = u32::MAX;
+        }
+
+        let units = |world: &World| world.foods.iter().map(|food| food.food_size).sum::<u32>();
+        let initial = units(&world);
+
+        for _ in 0..300 {
+            world.step();
+
+            let carried = world.ants.iter().filter(|ant| ant.food_in_hand).count() as u32;
+            let delivered: u32 = world.colonies.iter().map(|colony| colony.food_delivered).sum();
+            for food in &world.foods {
+                prop_assert!(food.food_size <= food.capacity);
+            }
+            prop_assert_eq!(initial.checked_sub(units(&world)), Some(carried + delivered));
+        }
+    }
+
+    #[test]
+    fn returning_ants_reach_the_colony_on_an_open_map(
+        seed in any::<u64>(),
+        starts in prop::collection::vec((1u16..40, 1u16..20), 1..10),
+    ) {
+        let mut world = World::with_seed(40, 20, seed);
+        world.food_spawn_rate = 0.0;
+        world.predator_spawn_rate = 0.0;
+
+        for (ant, &(x, y)) in world.ants.iter_mut().zip(&starts) {
+            ant.position = Position::new(x, y);
+            ant.mode = AntMode::Returning;
+            ant.food_in_hand = true;
+            ant.lifespan = u32::MAX;
+            ant.starvation = u32::MAX;
+        }
+        let carriers: Vec<u64> = world.ants.iter().take(starts.len()).map(|ant| ant.id).collect();
+
+        for _ in 0..1000 {
+            world.step();
+        }
+
+        for id in carriers {
+            let ant = world.ant(id).expect("carriers do not die");
+            prop_assert!(!ant.food_in_hand, "ant {} still carries food at {:?}", id, ant.position);
+        }
+    }
+}
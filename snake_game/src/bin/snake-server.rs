@@ -0,0 +1,110 @@
+//! Serves a game of snake to players on the network. They join it with
+//! `snake_game --connect <host:port>`; the game starts once all have.
+
+use snake_game::mode::DEFAULT_TIME_LIMIT;
+use snake_game::net::{self, Server};
+use snake_game::{Level, Mode, Replay};
+use std::error::Error;
+use std::net::TcpListener;
+use std::time::Duration;
+
+//the value following `flag` on the command line, if any.
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+//the mode to serve: `--level <name or file>`, or `--mode
+//<classic|wrap|speed|timed>` with `--time <seconds>` for timed.
+fn mode_from_args() -> Result<Mode, Box<dyn Error>> {
+    if let Some(level) = arg_value("--level") {
+        return Ok(Mode::Level(Level::find(&level)?));
+    }
+
+    let name = arg_value("--mode").unwrap_or_else(|| "classic".to_string());
+    let time_limit = match arg_value("--time") {
+        Some(seconds) => Duration::from_secs(seconds.parse()?),
+        None => DEFAULT_TIME_LIMIT,
+    };
+    Mode::named(&name, time_limit).ok_or_else(|| {
+        format!(
+            "unknown mode {:?}, expected classic, wrap, speed or timed",
+            name
+        )
+        .into()
+    })
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    //`--bind <address>` and `--port <port>` is where the players connect to.
+    let address = arg_value("--bind").unwrap_or_else(|| "0.0.0.0".to_string());
+    let port = arg_value("--port").map_or(Ok(net::DEFAULT_PORT), |port| port.parse())?;
+
+    //`--players <n>` is how many join before the game starts, `--seed <n>`
+    //picks the food placement, `--tick <ms>` fixes the length of a tick.
+    let players: usize = arg_value("--players").map_or(Ok(2), |players| players.parse())?;
+    if players == 0 {
+        return Err("--players takes at least 1".into());
+    }
+    let seed = match arg_value("--seed") {
+        Some(seed) => seed.parse()?,
+        None => rand::random(),
+    };
+    let tick = match arg_value("--tick") {
+        Some(millis) => Some(Duration::from_millis(millis.parse()?)),
+        None => None,
+    };
+
+    let mode = mode_from_args()?;
+    let (width, height, rules) = mode.setup(30, 20);
+    let replay = Replay {
+        players,
+        ..Replay::with_rules(seed, width, height, rules)
+    };
+
+    let listener = TcpListener::bind((address.as_str(), port))?;
+    println!(
+        "Serving {} for {} players on {}",
+        mode,
+        players,
+        listener.local_addr()?
+    );
+
+    let mut server = Server::new(listener, replay);
+    server.tick = tick;
+    while server.joined() < players {
+        let (player, name) = server.accept()?;
+        println!("Player {} joined: {}", player + 1, name);
+    }
+
+    println!("Game on!");
+    let game = server.run()?;
+
+    if !game.game_over {
+        println!("Everyone left after {} ticks.", game.tick);
+    } else if players == 1 {
+        println!("Game over! Score: {}", game.top_score());
+    } else {
+        match game.winner() {
+            Some(player) => println!("Player {} Wins! Score: {}", player + 1, game.top_score()),
+            None => println!("Draw!"),
+        }
+    }
+
+    //`--record <file>` saves the game for `snake_game --replay`.
+    if let Some(path) = arg_value("--record") {
+        server.replay.save(path)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,157 @@
+use rand::Rng;
+use std::collections::HashSet;
+
+use crate::game::{Position, Snake};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoodKind {
+    /// Always on the board, replaced as soon as it is eaten.
+    Regular,
+    /// Turns up now and then, is worth more and vanishes after a while.
+    Bonus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Food {
+    pub position: Position,
+    pub kind: FoodKind,
+    /// Tick at which uneaten bonus food disappears.
+    pub expires_at: Option<u64>,
+}
+
+/// How much food there is and what it is worth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FoodPolicy {
+    /// Regular food on the board at the same time.
+    pub count: usize,
+    pub points: u16,
+    /// Chance per tick of bonus food turning up while there is none.
+    pub bonus_chance: f64,
+    pub bonus_points: u16,
+    /// Ticks bonus food stays on the board.
+    pub bonus_ticks: u64,
+}
+
+impl Default for FoodPolicy {
+    fn default() -> Self {
+        FoodPolicy {
+            count: 1,
+            points: 1,
+            bonus_chance: 0.02,
+            bonus_points: 5,
+            bonus_ticks: 40,
+        }
+    }
+}
+
+impl FoodPolicy {
+    pub fn points(&self, kind: FoodKind) -> u16 {
+        match kind {
+            FoodKind::Regular => self.points,
+            FoodKind::Bonus => self.bonus_points,
+        }
+    }
+}
+
+/// A random interior cell free of snakes, food and obstacles, `None` if there is none.
+pub(crate) fn free_cell<R: Rng + ?Sized>(
+    width: u16,
+    height: u16,
+    snakes: &[Snake],
+    foods: &[Food],
+    obstacles: &HashSet<Position>,
+    rng: &mut R,
+) -> Option<Position> {
+    let taken = |position: &Position| {
+        snakes.iter().any(|snake| snake.body.contains(position))
+            || obstacles.contains(position)
+            || foods.iter().any(|food| food.position == *position)
+    };
+
+    if width < 2 || height < 2 {
+        return None;
+    }
+
+    //rejection sampling is fast while the board is mostly empty, the scan covers the rest.
+    for _ in 0..32 {
+        let position = Position {
+            x: rng.gen_range(1..width),
+            y: rng.gen_range(1..height),
+        };
+
+        if !taken(&position) {
+            return Some(position);
+        }
+    }
+
+    let cells: Vec<Position> = (1..height)
+        .flat_map(|y| (1..width).map(move |x| Position { x, y }))
+        .filter(|position| !taken(position))
+        .collect();
+    if cells.is_empty() {
+        return None;
+    }
+
+    Some(cells[rng.gen_range(0..cells.len())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn snake(cells: &[(u16, u16)]) -> Snake {
+        let mut snake = Snake::new(
+            Position {
+                x: cells[0].0,
+                y: cells[0].1,
+            },
+            Direction::Right,
+        );
+        snake
+            .body
+            .extend(cells[1..].iter().map(|&(x, y)| Position { x, y }));
+        snake
+    }
+
+    #[test]
+    fn only_free_interior_cells_are_picked() {
+        let snakes = [snake(&[(1, 1), (2, 1), (3, 1), (3, 2), (3, 3)])];
+        let foods = [Food {
+            position: Position { x: 5, y: 4 },
+            kind: FoodKind::Regular,
+            expires_at: None,
+        }];
+        let obstacles = HashSet::from([Position { x: 6, y: 2 }, Position { x: 1, y: 3 }]);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for _ in 0..1000 {
+            let cell = free_cell(8, 6, &snakes, &foods, &obstacles, &mut rng).unwrap();
+
+            assert!((1..8).contains(&cell.x) && (1..6).contains(&cell.y));
+            assert!(!snakes[0].body.contains(&cell));
+            assert!(!obstacles.contains(&cell));
+            assert_ne!(cell, foods[0].position);
+        }
+    }
+
+    #[test]
+    fn a_full_board_has_no_free_cell() {
+        //a 3 by 2 inside: the snake takes five cells, food the sixth.
+        let snakes = [snake(&[(1, 1), (2, 1), (3, 1), (3, 2), (2, 2)])];
+        let foods = [Food {
+            position: Position { x: 1, y: 2 },
+            kind: FoodKind::Regular,
+            expires_at: None,
+        }];
+        let mut rng = StdRng::seed_from_u64(5);
+
+        assert_eq!(
+            free_cell(4, 3, &snakes, &foods, &HashSet::new(), &mut rng),
+            None
+        );
+        assert!(free_cell(4, 3, &snakes, &[], &HashSet::new(), &mut rng).is_some());
+    }
+}
@@ -0,0 +1,921 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::food::{self, Food, FoodKind, FoodPolicy};
+use crate::mode::Rules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "Up" => Ok(Direction::Up),
+            "Down" => Ok(Direction::Down),
+            "Left" => Ok(Direction::Left),
+            "Right" => Ok(Direction::Right),
+            _ => Err(format!("unknown direction '{}'", text)),
+        }
+    }
+}
+
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+/// What a snake ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    Wall,
+    /// A wall inside the board.
+    Obstacle,
+    /// The snake ran into its own body.
+    Itself,
+    /// The body of another snake, alive or dead. Holds its player.
+    Snake(usize),
+    /// Another snake's head, moving into the same cell. Both die. Holds the
+    /// other player.
+    HeadOn(usize),
+}
+
+/// Something that happened during a step, for front ends to react to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A snake took a step without eating. Holds its player.
+    Moved(usize),
+    /// A snake ate and grew. Holds its player, the kind of food and its new
+    /// score.
+    Ate(usize, FoodKind, u16),
+    /// New food was put down.
+    FoodPlaced(Food),
+    /// Bonus food was left uneaten for too long.
+    FoodExpired(Food),
+    /// The snakes fill the whole board. The game is over.
+    Won,
+    /// A snake died. Holds its player.
+    Died(usize, Collision),
+    /// The time limit is up. The game is over.
+    TimeUp,
+}
+
+/// Everything that happened during one step, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Events {
+    events: Vec<Event>,
+}
+
+impl Events {
+    fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn contains(&self, event: &Event) -> bool {
+        self.events.contains(event)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The collision that killed `player` during the step, if any.
+    pub fn death(&self, player: usize) -> Option<Collision> {
+        self.events.iter().find_map(|event| match event {
+            Event::Died(dead, collision) if *dead == player => Some(*collision),
+            _ => None,
+        })
+    }
+}
+
+/// Turns kept waiting at most. Presses beyond that are dropped.
+pub const MAX_QUEUED_TURNS: usize = 3;
+
+/// One player's snake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snake {
+    /// Head first.
+    pub body: VecDeque<Position>,
+    pub direction: Direction,
+    /// Turns asked for but not taken yet, oldest first.
+    pub inputs: VecDeque<Direction>,
+    pub score: u16,
+    /// What killed the snake. A dead snake stays where it died and is in
+    /// the way of the others.
+    pub death: Option<Collision>,
+}
+
+impl Snake {
+    /// A snake of length one.
+    pub fn new(head: Position, direction: Direction) -> Self {
+        Snake {
+            body: VecDeque::from([head]),
+            direction,
+            inputs: VecDeque::new(),
+            score: 0,
+            death: None,
+        }
+    }
+
+    pub fn head(&self) -> Option<Position> {
+        self.body.front().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+
+    /// Queues a turn for the coming steps. Pressing the direction already
+    /// waiting at the end of the queue again does nothing.
+    pub fn queue_input(&mut self, direction: Direction) {
+        if self.inputs.back() == Some(&direction) || self.inputs.len() >= MAX_QUEUED_TURNS {
+            return;
+        }
+
+        self.inputs.push_back(direction);
+    }
+
+    //takes the first queued turn that changes the heading. Turns that would
+    //not, straight on or back into the neck, are dropped on the way.
+    fn take_turn(&mut self) {
+        while let Some(direction) = self.inputs.pop_front() {
+            if direction != self.direction && direction != self.direction.opposite() {
+                self.direction = direction;
+                return;
+            }
+        }
+    }
+}
+
+/// The game state. The border lies on x = 0, x = `width`, y = 0 and
+/// y = `height`; the snakes move in between. Every random draw goes through
+/// `rng`, so a game is reproduced by its seed and its inputs.
+#[derive(Debug, Clone)]
+pub struct SnakeGame {
+    /// One snake per player, the first player's first.
+    pub snakes: Vec<Snake>,
+    pub foods: Vec<Food>,
+    pub food_policy: FoodPolicy,
+    pub width: u16,
+    pub height: u16,
+    pub game_over: bool,
+    /// Whether the game ended with the board full rather than a collision.
+    pub won: bool,
+    /// Steps taken so far.
+    pub tick: u64,
+    pub rules: Rules,
+    rng: StdRng,
+}
+
+impl SnakeGame {
+    /// A game seeded from the OS.
+    pub fn new(width: u16, height: u16) -> Self {
+        SnakeGame::with_rng(width, height, StdRng::from_entropy())
+    }
+
+    /// Convenience constructor for reproducible games.
+    pub fn with_seed(width: u16, height: u16, seed: u64) -> Self {
+        SnakeGame::with_rng(width, height, StdRng::seed_from_u64(seed))
+    }
+
+    /// A game whose randomness is entirely driven by `rng`.
+    pub fn with_rng(width: u16, height: u16, rng: StdRng) -> Self {
+        SnakeGame::with_food(width, height, FoodPolicy::default(), rng)
+    }
+
+    /// Like `with_rng`, with food put down and scored by `food_policy`.
+    pub fn with_food(width: u16, height: u16, food_policy: FoodPolicy, rng: StdRng) -> Self {
+        SnakeGame::with_rules(width, height, Rules::default(), food_policy, rng)
+    }
+
+    /// Like `with_food`, played by `rules`. Obstacles must leave the middle
+    /// of the board open for the snake.
+    pub fn with_rules(
+        width: u16,
+        height: u16,
+        rules: Rules,
+        food_policy: FoodPolicy,
+        rng: StdRng,
+    ) -> Self {
+        SnakeGame::with_players(width, height, 1, rules, food_policy, rng)
+    }
+
+    /// Like `with_rules`, with a snake for each of `players`. A single
+    /// snake starts in the middle heading right; several start spread over
+    /// the rows, alternately on the left heading right and on the right
+    /// heading left.
+    pub fn with_players(
+        width: u16,
+        height: u16,
+        players: usize,
+        rules: Rules,
+        food_policy: FoodPolicy,
+        rng: StdRng,
+    ) -> Self {
+        let mut game = SnakeGame {
+            snakes: Vec::new(),
+            foods: Vec::new(),
+            food_policy,
+            width,
+            height,
+            game_over: false,
+            won: false,
+            tick: 0,
+            rules,
+            rng,
+        };
+
+        for player in 0..players.max(1) {
+            let (head, direction) = game.start(player, players);
+            game.snakes.push(Snake::new(head, direction));
+        }
+
+        game.refill_food(&mut Events::default());
+        game
+    }
+
+    //where `player` of `players` starts. Walls and snakes already placed are
+    //avoided by moving along the row.
+    fn start(&self, player: usize, players: usize) -> (Position, Direction) {
+        let (x, y, direction) = if players <= 1 {
+            (self.width / 2, self.height / 2, Direction::Right)
+        } else {
+            let y = (player + 1) * usize::from(self.height) / (players + 1);
+            let y = (y as u16).clamp(1, self.height.saturating_sub(1).max(1));
+            if player.is_multiple_of(2) {
+                (self.width / 4, y, Direction::Right)
+            } else {
+                (self.width - self.width / 4, y, Direction::Left)
+            }
+        };
+
+        let taken = |position: &Position| {
+            self.wall_at(*position).is_some()
+                || self
+                    .snakes
+                    .iter()
+                    .any(|snake| snake.body.contains(position))
+        };
+        let head = (0..self.width)
+            .flat_map(|offset| [x.checked_add(offset), x.checked_sub(offset)])
+            .flatten()
+            .map(|x| Position { x, y })
+            .find(|position| !taken(position))
+            .unwrap_or(Position { x, y });
+
+        (head, direction)
+    }
+
+    //tops the regular food up to the policy's count, as far as there is room.
+    fn refill_food(&mut self, events: &mut Events) {
+        let regular = |foods: &[Food]| {
+            foods
+                .iter()
+                .filter(|food| food.kind == FoodKind::Regular)
+                .count()
+        };
+
+        while regular(&self.foods) < self.food_policy.count {
+            let Some(position) = self.free_cell() else {
+                return;
+            };
+
+            let food = Food {
+                position,
+                kind: FoodKind::Regular,
+                expires_at: None,
+            };
+            self.foods.push(food);
+            events.push(Event::FoodPlaced(food));
+        }
+    }
+
+    fn free_cell(&mut self) -> Option<Position> {
+        food::free_cell(
+            self.width,
+            self.height,
+            &self.snakes,
+            &self.foods,
+            &self.rules.obstacles,
+            &mut self.rng,
+        )
+    }
+
+    /// Open cells inside the border, i.e. the longest the snakes can get
+    /// together.
+    pub fn interior_cells(&self) -> usize {
+        let inside =
+            usize::from(self.width.saturating_sub(1)) * usize::from(self.height.saturating_sub(1));
+        inside.saturating_sub(self.rules.obstacles.len())
+    }
+
+    /// The cell one step from `position` in `direction`. Off the board that
+    /// is a border cell, or with wrap-around the cell on the far side.
+    pub fn ahead(&self, position: Position, direction: Direction) -> Position {
+        let ahead = match direction {
+            //saturating_sub subtracts 1 from the y without causing any underflow to happen.
+            //the origin is at the top left. Hence the substraction instead of addition.
+            Direction::Up => Position {
+                x: position.x,
+                y: position.y.saturating_sub(1),
+            },
+            Direction::Down => Position {
+                x: position.x,
+                y: position.y + 1,
+            },
+            Direction::Left => Position {
+                x: position.x.saturating_sub(1),
+                y: position.y,
+            },
+            Direction::Right => Position {
+                x: position.x + 1,
+                y: position.y,
+            },
+        };
+
+        if !self.rules.wrap {
+            return ahead;
+        }
+
+        let wrap = |value: u16, size: u16| match value {
+            0 => size.saturating_sub(1),
+            value if value >= size => 1,
+            value => value,
+        };
+        Position {
+            x: wrap(ahead.x, self.width),
+            y: wrap(ahead.y, self.height),
+        }
+    }
+
+    /// What a snake would run into at `position`, not counting snakes.
+    pub fn wall_at(&self, position: Position) -> Option<Collision> {
+        if position.x == 0
+            || position.y == 0
+            || position.x >= self.width
+            || position.y >= self.height
+        {
+            Some(Collision::Wall)
+        } else if self.rules.obstacles.contains(&position) {
+            Some(Collision::Obstacle)
+        } else {
+            None
+        }
+    }
+
+    /// Ticks until the time limit is up, if there is one.
+    pub fn ticks_left(&self) -> Option<u64> {
+        self.rules
+            .time_limit
+            .map(|limit| limit.saturating_sub(self.tick))
+    }
+
+    /// Queues a turn for `player`'s snake.
+    pub fn queue_input(&mut self, player: usize, direction: Direction) {
+        if let Some(snake) = self.snakes.get_mut(player) {
+            snake.queue_input(direction);
+        }
+    }
+
+    /// The highest score of any snake.
+    pub fn top_score(&self) -> u16 {
+        self.snakes
+            .iter()
+            .map(|snake| snake.score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Snakes still alive.
+    pub fn alive(&self) -> usize {
+        self.snakes.iter().filter(|snake| snake.is_alive()).count()
+    }
+
+    /// The player who won a finished game of several snakes: the last one
+    /// alive, or, when the game ended with several alive or the last ones
+    /// died together, the one with the highest score. `None` for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.game_over {
+            return None;
+        }
+
+        let contenders: Vec<usize> = match self.alive() {
+            0 => (0..self.snakes.len()).collect(),
+            _ => (0..self.snakes.len())
+                .filter(|&player| self.snakes[player].is_alive())
+                .collect(),
+        };
+        let best = contenders
+            .iter()
+            .map(|&player| self.snakes[player].score)
+            .max()?;
+        let mut leaders = contenders
+            .into_iter()
+            .filter(|&player| self.snakes[player].score == best);
+
+        match (leaders.next(), leaders.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        }
+    }
+
+    //what the head of `player` runs into at `head`, given where every head
+    //is going. Bodies are checked before any tail moves on, except the
+    //snake's own tail: food never lies there, so it moves on as the head
+    //moves up.
+    fn collision(
+        &self,
+        player: usize,
+        head: Position,
+        heads: &[Option<Position>],
+    ) -> Option<Collision> {
+        if let Some(collision) = self.wall_at(head) {
+            return Some(collision);
+        }
+
+        let head_on = heads
+            .iter()
+            .enumerate()
+            .find(|&(other, other_head)| other != player && *other_head == Some(head));
+        if let Some((other, _)) = head_on {
+            return Some(Collision::HeadOn(other));
+        }
+
+        self.snakes
+            .iter()
+            .enumerate()
+            .position(|(owner, snake)| {
+                let leaving = if owner == player { 1 } else { 0 };
+                snake
+                    .body
+                    .range(..snake.len().saturating_sub(leaving))
+                    .any(|segment| *segment == head)
+            })
+            .map(|owner| {
+                if owner == player {
+                    Collision::Itself
+                } else {
+                    Collision::Snake(owner)
+                }
+            })
+    }
+
+    /// Advances the game by one tick. Every snake alive takes at most one
+    /// queued turn, the rest waits for later ticks, and then all of them
+    /// move at once.
+    ///
+    /// The game is over once its only snake dies, or with several snakes
+    /// once at most one is left.
+    pub fn step(&mut self) -> Events {
+        let mut events = Events::default();
+
+        if self.game_over {
+            return events;
+        }
+
+        for snake in self.snakes.iter_mut().filter(|snake| snake.is_alive()) {
+            snake.take_turn();
+        }
+        self.tick += 1;
+
+        let heads: Vec<Option<Position>> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                let head = snake.head().filter(|_| snake.is_alive())?;
+                Some(self.ahead(head, snake.direction))
+            })
+            .collect();
+
+        //every collision is found before anyone moves, so the order of the
+        //snakes does not matter.
+        let deaths: Vec<(usize, Collision)> = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(player, head)| {
+                let collision = self.collision(player, (*head)?, &heads)?;
+                Some((player, collision))
+            })
+            .collect();
+        for (player, collision) in deaths {
+            self.snakes[player].death = Some(collision);
+            events.push(Event::Died(player, collision));
+        }
+
+        for (player, head) in heads.into_iter().enumerate() {
+            let Some(head) = head.filter(|_| self.snakes[player].is_alive()) else {
+                continue;
+            };
+            self.snakes[player].body.push_front(head);
+
+            match self.foods.iter().position(|food| food.position == head) {
+                Some(index) => {
+                    let food = self.foods.remove(index);
+                    let snake = &mut self.snakes[player];
+                    snake.score = snake
+                        .score
+                        .saturating_add(self.food_policy.points(food.kind));
+                    events.push(Event::Ate(player, food.kind, snake.score));
+                }
+                None => {
+                    self.snakes[player].body.pop_back();
+                    events.push(Event::Moved(player));
+                }
+            }
+        }
+
+        let alive = self.alive();
+        if alive == 0 || (self.snakes.len() > 1 && alive <= 1) {
+            self.game_over = true;
+            return events;
+        }
+
+        //nowhere left to go: the board is won.
+        let occupied: usize = self.snakes.iter().map(Snake::len).sum();
+        if occupied >= self.interior_cells() {
+            self.game_over = true;
+            self.won = true;
+            events.push(Event::Won);
+            return events;
+        }
+
+        //out of time: the game ends with whatever was eaten so far.
+        if self.ticks_left() == Some(0) {
+            self.game_over = true;
+            events.push(Event::TimeUp);
+            return events;
+        }
+
+        let tick = self.tick;
+        let (expired, kept): (Vec<Food>, Vec<Food>) = self
+            .foods
+            .drain(..)
+            .partition(|food| food.expires_at.is_some_and(|at| at <= tick));
+        self.foods = kept;
+        for food in expired {
+            events.push(Event::FoodExpired(food));
+        }
+
+        self.refill_food(&mut events);
+
+        let has_bonus = self.foods.iter().any(|food| food.kind == FoodKind::Bonus);
+        if !has_bonus
+            && self
+                .rng
+                .gen_bool(self.food_policy.bonus_chance.clamp(0.0, 1.0))
+        {
+            if let Some(position) = self.free_cell() {
+                let food = Food {
+                    position,
+                    kind: FoodKind::Bonus,
+                    expires_at: Some(tick + self.food_policy.bonus_ticks),
+                };
+                self.foods.push(food);
+                events.push(Event::FoodPlaced(food));
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a game with the snake in the middle of an empty board, heading right.
+    fn game() -> SnakeGame {
+        let mut game = SnakeGame::with_seed(20, 20, 1);
+        game.food_policy.bonus_chance = 0.0;
+        game.foods.clear();
+        game.food_policy.count = 0;
+        game
+    }
+
+    //the same with two snakes, the first heading right on the left, the
+    //second heading left on the right.
+    fn duel() -> SnakeGame {
+        let mut game = SnakeGame::with_players(
+            20,
+            20,
+            2,
+            Rules::default(),
+            FoodPolicy {
+                count: 0,
+                bonus_chance: 0.0,
+                ..FoodPolicy::default()
+            },
+            StdRng::seed_from_u64(1),
+        );
+        game.foods.clear();
+        game
+    }
+
+    fn head(game: &SnakeGame) -> Position {
+        game.snakes[0].body[0]
+    }
+
+    #[test]
+    fn rapid_up_and_left_while_moving_right_take_two_ticks() {
+        let mut game = game();
+        let start = head(&game);
+
+        game.queue_input(0, Direction::Up);
+        game.queue_input(0, Direction::Left);
+
+        let events = game.step();
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        assert_eq!(
+            head(&game),
+            Position {
+                x: start.x,
+                y: start.y - 1
+            }
+        );
+        assert_eq!(game.snakes[0].inputs, VecDeque::from([Direction::Left]));
+        assert_eq!(events.death(0), None);
+
+        let events = game.step();
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        assert_eq!(
+            head(&game),
+            Position {
+                x: start.x - 1,
+                y: start.y - 1
+            }
+        );
+        assert!(game.snakes[0].inputs.is_empty());
+        assert_eq!(events.death(0), None);
+    }
+
+    #[test]
+    fn a_long_snake_survives_rapid_up_and_left() {
+        let mut game = game();
+        let start = head(&game);
+        //a body trailing to the left, so reversing would bite the neck.
+        for x in 1..=3 {
+            game.snakes[0].body.push_back(Position {
+                x: start.x - x,
+                y: start.y,
+            });
+        }
+
+        game.queue_input(0, Direction::Up);
+        game.queue_input(0, Direction::Left);
+        game.step();
+        game.step();
+
+        assert!(!game.game_over);
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+    }
+
+    #[test]
+    fn reversing_is_dropped_and_the_next_turn_taken() {
+        let mut game = game();
+
+        game.queue_input(0, Direction::Left);
+        game.queue_input(0, Direction::Up);
+        game.step();
+
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        assert!(game.snakes[0].inputs.is_empty());
+    }
+
+    #[test]
+    fn one_turn_per_tick_with_the_rest_carried_over() {
+        let mut game = game();
+
+        game.queue_input(0, Direction::Up);
+        game.queue_input(0, Direction::Left);
+        game.queue_input(0, Direction::Down);
+        game.queue_input(0, Direction::Right);
+
+        let mut directions = Vec::new();
+        for _ in 0..4 {
+            game.step();
+            directions.push(game.snakes[0].direction);
+        }
+
+        //the fourth press did not fit in the queue.
+        assert_eq!(
+            directions,
+            [
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Down
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_into_the_tail_cell_as_the_tail_leaves_is_not_a_death() {
+        //curled up in a square, the head just below the end of the tail.
+        let curled = |cells: &[(u16, u16)]| {
+            let mut game = game();
+            game.snakes[0].body = cells.iter().map(|&(x, y)| Position { x, y }).collect();
+            game.snakes[0].direction = Direction::Left;
+            game.queue_input(0, Direction::Up);
+            game
+        };
+
+        let mut game = curled(&[(5, 6), (6, 6), (6, 5), (5, 5)]);
+        let events = game.step();
+
+        assert_eq!(events.death(0), None);
+        assert!(!game.game_over);
+        assert_eq!(head(&game), Position { x: 5, y: 5 });
+        assert_eq!(game.snakes[0].len(), 4);
+
+        //one segment longer, the same cell is still part of the body.
+        let mut game = curled(&[(5, 6), (6, 6), (6, 5), (5, 5), (4, 5)]);
+        let events = game.step();
+
+        assert_eq!(events.death(0), Some(Collision::Itself));
+    }
+
+    #[test]
+    fn wrapping_comes_back_in_on_the_far_side() {
+        let mut game = game();
+        game.rules.wrap = true;
+        let start = head(&game);
+
+        for _ in start.x..game.width {
+            game.step();
+        }
+
+        assert!(!game.game_over);
+        assert_eq!(head(&game), Position { x: 1, y: start.y });
+    }
+
+    #[test]
+    fn running_into_an_obstacle_ends_the_game() {
+        let mut game = game();
+        let start = head(&game);
+        game.rules.obstacles.insert(Position {
+            x: start.x + 2,
+            y: start.y,
+        });
+
+        assert_eq!(game.step().death(0), None);
+        assert_eq!(game.step().death(0), Some(Collision::Obstacle));
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn the_game_ends_when_time_is_up() {
+        let mut game = game();
+        game.rules.time_limit = Some(3);
+
+        assert!(!game.step().contains(&Event::TimeUp));
+        assert!(!game.step().contains(&Event::TimeUp));
+        assert!(game.step().contains(&Event::TimeUp));
+        assert!(game.game_over && !game.won);
+        assert_eq!(game.ticks_left(), Some(0));
+    }
+
+    #[test]
+    fn heads_meeting_in_one_cell_both_die() {
+        let mut game = duel();
+        let row = game.snakes[0].body[0].y;
+        game.snakes[0].body = VecDeque::from([Position { x: 8, y: row }]);
+        game.snakes[1].body = VecDeque::from([Position { x: 10, y: row }]);
+
+        let events = game.step();
+
+        assert_eq!(events.death(0), Some(Collision::HeadOn(1)));
+        assert_eq!(events.death(1), Some(Collision::HeadOn(0)));
+        assert!(game.game_over);
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn running_into_another_body_kills_only_the_runner() {
+        let mut game = duel();
+        game.snakes[0].body = VecDeque::from([Position { x: 5, y: 5 }]);
+        //the second snake crosses right in front of the first.
+        game.snakes[1].body = VecDeque::from([
+            Position { x: 6, y: 5 },
+            Position { x: 6, y: 4 },
+            Position { x: 6, y: 3 },
+        ]);
+        game.snakes[1].direction = Direction::Down;
+        game.snakes[1].score = 1;
+
+        let events = game.step();
+
+        assert_eq!(events.death(0), Some(Collision::Snake(1)));
+        assert_eq!(events.death(1), None);
+        assert!(game.game_over);
+        assert_eq!(game.winner(), Some(1));
+    }
+
+    #[test]
+    fn every_snake_takes_its_own_turns() {
+        let mut game = duel();
+
+        game.queue_input(0, Direction::Up);
+        game.queue_input(1, Direction::Down);
+        game.step();
+
+        assert_eq!(game.snakes[0].direction, Direction::Up);
+        assert_eq!(game.snakes[1].direction, Direction::Down);
+        assert_eq!(game.alive(), 2);
+    }
+
+    #[test]
+    fn a_head_may_follow_its_own_tail_but_not_another() {
+        let mut game = duel();
+        //the first snake curled up in a square, its head just below its tail.
+        game.snakes[0].body = [(5, 6), (6, 6), (6, 5), (5, 5)]
+            .into_iter()
+            .map(|(x, y)| Position { x, y })
+            .collect();
+        game.snakes[0].direction = Direction::Left;
+        game.queue_input(0, Direction::Up);
+
+        let events = game.step();
+
+        assert_eq!(events.death(0), None);
+        assert_eq!(head(&game), Position { x: 5, y: 5 });
+        assert_eq!(game.snakes[0].len(), 4);
+
+        //the tail has moved on to (6, 5), the second snake heads left into it.
+        game.snakes[1].body = VecDeque::from([Position { x: 7, y: 5 }]);
+        game.snakes[1].direction = Direction::Left;
+        let events = game.step();
+
+        assert_eq!(events.death(0), None);
+        assert_eq!(events.death(1), Some(Collision::Snake(0)));
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        //a 3 by 2 inside, the snake winding through five cells towards the food in the sixth.
+        let mut game = SnakeGame::with_seed(4, 3, 1);
+        game.food_policy.bonus_chance = 0.0;
+        game.snakes[0].direction = Direction::Left;
+        game.snakes[0].body = [(2, 2), (3, 2), (3, 1), (2, 1), (1, 1)]
+            .into_iter()
+            .map(|(x, y)| Position { x, y })
+            .collect();
+        game.foods = vec![Food {
+            position: Position { x: 1, y: 2 },
+            kind: FoodKind::Regular,
+            expires_at: None,
+        }];
+
+        let events = game.step();
+
+        assert_eq!(game.snakes[0].len(), game.interior_cells());
+        assert!(game.won && game.game_over);
+        assert!(events.contains(&Event::Won));
+        assert_eq!(events.death(0), None);
+        assert!(game.foods.is_empty());
+    }
+}
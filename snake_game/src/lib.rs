@@ -0,0 +1,21 @@
+//! Rules of the snake game. Nothing in here writes to the terminal: a
+//! `SnakeGame` is advanced with `step` and shown by any `Renderer`, e.g. the
+//! terminal front end of the binary or `Headless` in tests and CI.
+
+pub mod food;
+pub mod game;
+pub mod mode;
+pub mod net;
+pub mod render;
+pub mod replay;
+pub mod scores;
+pub mod strategy;
+
+pub use food::{Food, FoodKind, FoodPolicy};
+pub use game::{Collision, Direction, Event, Events, Position, SnakeGame};
+pub use mode::{Level, LevelError, Mode, Rules};
+pub use net::{Client, NetError, Server};
+pub use render::{Headless, Renderer};
+pub use replay::{Replay, ReplayError, REPLAY_VERSION};
+pub use scores::{HighScores, ScoreEntry, ScoresError, Stats};
+pub use strategy::{Hamiltonian, ShortestPath, SnakeStrategy};
@@ -1,201 +1,590 @@
-use crossterm::{cursor, event::{self, Event, KeyCode}, execute, terminal};
-use rand::Rng;
-use std::collections::VecDeque;
-use std::io::{stdout, Write};
+mod tui;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, terminal};
+use snake_game::mode::DEFAULT_TIME_LIMIT;
+use snake_game::scores::{self, HighScores, ScoreEntry};
+use snake_game::strategy::{self, Hamiltonian, ShortestPath, SnakeStrategy};
+use snake_game::{render, Client, Direction, Level, Mode, Renderer, Replay, SnakeGame};
+use std::error::Error;
+use std::fs;
+use std::io::{self, stdout, BufWriter};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
+use tui::TerminalRenderer;
+
+//puts the terminal into the state the game draws in, and back when dropped,
+//so the terminal is restored however the program ends.
+struct Screen;
 
-#[derive(Clone, Copy, PartialEq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        //by default, terminals are in cooked mode. In raw mode, the inputs like the arrow
+        //keys are handled by the program as opposed to the normal termina.
+        terminal::enable_raw_mode()?;
+        //enter an alternate state so that when the program exits, the original terminal
+        //remains the same.
+        execute!(stdout(), terminal::EnterAlternateScreen)?;
+        Ok(Screen)
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-struct Position {
-    x: u16,
-    y: u16
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(stdout(), terminal::LeaveAlternateScreen, cursor::Show);
+    }
 }
 
+//the value following `flag` on the command line, if any.
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
 
-struct SnakeGame {
-    
-    snake: VecDeque<Position>,
-    direction: Direction,
-    food: Position,
-    width: u16,
-    height: u16,
-    score: u16,
-    game_over: bool
+//waits out the rest of a tick. Returns `false` if the player pressed `q`.
+fn wait_for_quit(until: Instant) -> io::Result<bool> {
+    while let Some(timeout) = until.checked_duration_since(Instant::now()) {
+        if !event::poll(timeout)? {
+            break;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && key.code == KeyCode::Char('q') {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
 }
 
+type Terminal = TerminalRenderer<BufWriter<io::Stdout>>;
 
-impl SnakeGame {
-    
-    fn new(width:u16, height:u16) -> Self {
-        let mut snake = VecDeque::new();
-        snake.push_back(Position {x: width/2, y: height/2});
-        
-        let food = Position {
-            x: rand::thread_rng().gen_range(1..width),
-            y: rand::thread_rng().gen_range(1..height)
-        };
+//who steers each snake: the keyboard, or a strategy.
+type Pilots = Vec<Option<Box<dyn SnakeStrategy>>>;
 
-        SnakeGame{
-            snake,
-            direction: Direction::Right,
-            food,
-            width, 
-            height,
-            score: 0,
-            game_over: false
-        }
+//the player and turn a key stands for: the arrows steer the first snake,
+//WASD the second one, or the first one in a game for one.
+fn key_input(code: KeyCode, humans: usize) -> Option<(usize, Direction)> {
+    let wasd = if humans > 1 { 1 } else { 0 };
+
+    match code {
+        KeyCode::Up => Some((0, Direction::Up)),
+        KeyCode::Down => Some((0, Direction::Down)),
+        KeyCode::Left => Some((0, Direction::Left)),
+        KeyCode::Right => Some((0, Direction::Right)),
+        KeyCode::Char('w') => Some((wasd, Direction::Up)),
+        KeyCode::Char('s') => Some((wasd, Direction::Down)),
+        KeyCode::Char('a') => Some((wasd, Direction::Left)),
+        KeyCode::Char('d') => Some((wasd, Direction::Right)),
+        _ => None,
     }
-    
-    fn render(&self) {
-        
-        execute!(stdout(), cursor::Hide).unwrap(); //hides the blinking cursor.
-        execute!(stdout(), terminal::Clear(terminal::ClearType::All)).unwrap(); //Clears the screen
-                                                                                //from the previous
-                                                                                //artifacts and
-                                                                                //leftovers.for 
-        //in order to draw the elements in the terminal, we have to move the cursor to the location
-        //before printing out the desired element.
-        for x in 0..=self.width {
-            execute!(stdout(), cursor::MoveTo(x, 0)).unwrap(); 
-            println!("#");
-        }
+}
+
+//plays a game, every snake steered from the keyboard or by its pilot,
+//noting every input in `replay`.
+fn play(
+    renderer: &mut Terminal,
+    game: &mut SnakeGame,
+    replay: &mut Replay,
+    pilots: &mut Pilots,
+) -> io::Result<()> {
+    renderer.render(game)?;
+
+    let humans = pilots.iter().filter(|pilot| pilot.is_none()).count();
+    let mut next_tick = Instant::now() + game.rules.tick_duration(game.top_score());
+
+    while !game.game_over {
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if key.code == KeyCode::Char('q') {
+                    break;
+                }
+
+                let Some((player, direction)) = key_input(key.code, humans) else {
+                    continue;
+                };
+                if !matches!(pilots.get(player), Some(None)) {
+                    continue;
+                }
 
-        for x in 0..=self.width {
-            execute!(stdout(), cursor::MoveTo(x, self.height)).unwrap();
-            println!("#");
+                //every press is queued, the game takes one turn per tick.
+                game.queue_input(player, direction);
+                replay.record(game.tick, Some((player, direction)));
+            }
+            continue;
         }
 
-        for y in 0..=self.height {
-            execute!(stdout(), cursor::MoveTo(0, y)).unwrap();
-            println!("#");
-            execute!(stdout(), cursor::MoveTo(self.width, y)).unwrap();
-            println!("#");
+        for (player, pilot) in pilots.iter_mut().enumerate() {
+            let Some(pilot) = pilot else {
+                continue;
+            };
+
+            //going straight on needs no input, which keeps the replay short.
+            if let Some(direction) = pilot
+                .next_direction(game, player)
+                .filter(|direction| *direction != game.snakes[player].direction)
+            {
+                game.queue_input(player, direction);
+                replay.record(game.tick, Some((player, direction)));
+            }
         }
 
-        for segment in &self.snake {
-            execute!(stdout(), cursor::MoveTo(segment.x, segment.y)).unwrap();
-            println!("o");
+        replay.record(game.tick, None);
+        game.step();
+        renderer.render(game)?;
+        next_tick = Instant::now() + game.rules.tick_duration(game.top_score());
+    }
+
+    Ok(())
+}
+
+//asks for a name below `lines`, the game over screen. Enter keeps the name
+//typed so far, escape the default.
+fn ask_name(renderer: &mut Terminal, lines: &[String], default: &str) -> io::Result<String> {
+    let mut name = String::new();
+
+    loop {
+        let mut screen = lines.to_vec();
+        screen.push(format!("New high score! Name: {}_", name));
+        renderer.show(&screen)?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Enter if name.trim().is_empty() => return Ok(default.to_string()),
+                KeyCode::Enter => return Ok(name),
+                KeyCode::Esc => return Ok(default.to_string()),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Char(c)
+                    if !c.is_control() && name.chars().count() < scores::MAX_NAME_LENGTH =>
+                {
+                    name.push(c);
+                }
+                _ => {}
+            }
         }
+    }
+}
 
-        execute!(stdout(), cursor::MoveTo(self.food.x, self.food.y)).unwrap();
-        println!("X");
+//what to do after a game.
+enum Next {
+    Again,
+    Menu,
+    Quit,
+}
 
-        execute!(stdout(), cursor::MoveTo(0, self.height+1)).unwrap();
-        println!("Score: {}", self.score);
+//waits on the game over screen for the player to choose what comes next.
+fn wait_for_next() -> io::Result<Next> {
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
 
-        stdout().flush().unwrap() //forces any pending buffer to be written to the terminal
-                                  //immedidately.
+            match key.code {
+                KeyCode::Char('r') => return Ok(Next::Again),
+                KeyCode::Char('m') => return Ok(Next::Menu),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Next::Quit),
+                _ => {}
+            }
+        }
     }
+}
+
+//the modes on the start menu.
+fn modes() -> Vec<Mode> {
+    let mut modes = vec![
+        Mode::Classic,
+        Mode::Wrap,
+        Mode::Speed,
+        Mode::Timed(DEFAULT_TIME_LIMIT),
+    ];
+    modes.extend(Level::builtin().into_iter().map(Mode::Level));
+    modes
+}
+
+//lets the player pick a mode. `None` if they quit instead.
+fn menu(renderer: &mut Terminal, modes: &[Mode], mut selected: usize) -> io::Result<Option<usize>> {
+    let names: Vec<String> = modes.iter().map(Mode::to_string).collect();
+
+    loop {
+        renderer.show(&render::menu_lines(&names, selected))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
 
-    fn update(&mut self) {
-        
-        if self.game_over {
-            return;
+            match key.code {
+                KeyCode::Up => selected = selected.checked_sub(1).unwrap_or(modes.len() - 1),
+                KeyCode::Down => selected = (selected + 1) % modes.len(),
+                KeyCode::Enter => return Ok(Some(selected)),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
         }
+    }
+}
 
-        let head = self.snake.front().unwrap();
-        let new_head = match self.direction {
-            //saturating_sub subtracts 1 from the y without causing any underflow to happen.
-            //the origin is at the top left. Hence the substraction instead of addition.
-            Direction::Up => Position {x: head.x, y: head.y.saturating_sub(1)},
-            Direction::Down => Position {x: head.x, y: head.y + 1},
-            Direction::Left => Position {x: head.x.saturating_sub(1), y: head.y},
-            Direction::Right => Position {x: head.x + 1, y: head.y}
-        };
+//the mode asked for on the command line, if any: `--level <name or file>`,
+//or `--mode <classic|wrap|speed|timed>` with `--time <seconds>` for timed.
+fn mode_from_args() -> Result<Option<Mode>, Box<dyn Error>> {
+    if let Some(level) = arg_value("--level") {
+        return Ok(Some(Mode::Level(Level::find(&level)?)));
+    }
+
+    let Some(name) = arg_value("--mode") else {
+        return Ok(None);
+    };
+    let time_limit = match arg_value("--time") {
+        Some(seconds) => Duration::from_secs(seconds.parse()?),
+        None => DEFAULT_TIME_LIMIT,
+    };
+    let mode = Mode::named(&name, time_limit).ok_or_else(|| {
+        format!(
+            "unknown mode {:?}, expected classic, wrap, speed or timed",
+            name
+        )
+    })?;
+
+    Ok(Some(mode))
+}
 
-        //wall collision check
-        if new_head.x == 0 || new_head.y == 0 || new_head.x == self.width || new_head.y == self.height {
-            self.game_over = true;
-            return;
+//shows a recorded game at the usual pace. Only quitting is honoured.
+fn watch(replay: &Replay) -> io::Result<SnakeGame> {
+    let _screen = Screen::enter()?;
+    let mut renderer = TerminalRenderer::new(BufWriter::new(stdout()));
+    renderer.render(&replay.start())?;
+
+    let mut result = Ok(true);
+    let game = replay.play(|game, _| {
+        result = renderer.render(game).and_then(|_| {
+            wait_for_quit(Instant::now() + game.rules.tick_duration(game.top_score()))
+        });
+        matches!(result, Ok(true))
+    });
+
+    result.map(|_| game)
+}
+
+//plays a game served by `snake-server` at `address`, steered with the
+//arrows or WASD.
+fn play_online(address: &str, name: &str) -> Result<SnakeGame, Box<dyn Error>> {
+    //joining waits for the other players, still in the normal terminal so
+    //the wait can be interrupted.
+    println!("Waiting for every player to join {}...", address);
+    let mut client = Client::connect(address, name)?;
+
+    let _screen = Screen::enter()?;
+    let mut renderer = TerminalRenderer::new(BufWriter::new(stdout()));
+    let player = client.player;
+    let screen = |game: &SnakeGame| {
+        let mut lines = render::lines(game);
+        lines.push(format!(
+            "You are P{} {}",
+            player + 1,
+            render::SNAKE_CHARS[player % render::SNAKE_CHARS.len()]
+        ));
+        lines
+    };
+    renderer.show(&screen(&client.game))?;
+
+    //the server keeps time: draw whatever it sends, pass every turn on.
+    while !client.game.game_over {
+        if client.update(Duration::from_millis(10))? {
+            renderer.show(&screen(&client.game))?;
         }
-        
-        //if the snake bites its own tail.
-        if self.snake.contains(&new_head){
-            self.game_over = true;
-            return;
+
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if key.code == KeyCode::Char('q') {
+                    return Ok(client.game);
+                }
+                if let Some((_, direction)) = key_input(key.code, 1) {
+                    client.send_input(direction)?;
+                }
+            }
         }
+    }
 
-        self.snake.push_front(new_head);
+    let mut lines = screen(&client.game);
+    lines.push(match client.game.winner() {
+        _ if client.game.snakes.len() == 1 && !client.game.won => "GAME OVER".to_string(),
+        Some(winner) if winner == player => "YOU WIN!".to_string(),
+        Some(winner) => format!("PLAYER {} WINS!", winner + 1),
+        None => "DRAW".to_string(),
+    });
+    lines.push("q: quit".to_string());
+    renderer.show(&lines)?;
+    while !matches!(wait_for_next()?, Next::Quit) {}
 
-        if new_head == self.food {
-            self.score += 1;
-            self.food = Position{
-                x: rand::thread_rng().gen_range(1..self.width),
-                y: rand::thread_rng().gen_range(1..self.height)
-            };
-        } else {
-            self.snake.pop_back();
-        }
+    Ok(client.game)
+}
 
+//the strategy called `name` on the command line.
+fn strategy(name: &str, width: u16, height: u16) -> Result<Box<dyn SnakeStrategy>, String> {
+    match name {
+        "shortest-path" => Ok(Box::new(ShortestPath)),
+        "hamiltonian" => Hamiltonian::new(width, height)
+            .map(|strategy| Box::new(strategy) as Box<dyn SnakeStrategy>)
+            .ok_or_else(|| "the board is too small for a hamiltonian cycle".to_string()),
+        _ => Err(format!(
+            "unknown strategy {:?}, expected shortest-path or hamiltonian",
+            name
+        )),
     }
-    
-    fn change_direction(&mut self, new_direction: Direction) {
-        //prevent reverse movements
-        if (self.direction == Direction::Up && new_direction == Direction::Down) ||
-           (self.direction == Direction::Down && new_direction == Direction::Up) ||
-           (self.direction == Direction::Left && new_direction == Direction::Right) ||
-           (self.direction == Direction::Right && new_direction == Direction::Left) 
-            {
-               return;
-            }
-        self.direction = new_direction;
+}
+
+//plays `games` seeded games with every strategy and prints how they did.
+fn benchmark(
+    games: u64,
+    first_seed: u64,
+    max_ticks: u64,
+    width: u16,
+    height: u16,
+) -> Result<(), String> {
+    println!(
+        "{:<14} {:>6} {:>10} {:>10} {:>6} {:>9}",
+        "strategy", "games", "avg score", "avg ticks", "wins", "timeouts"
+    );
+
+    for name in ["shortest-path", "hamiltonian"] {
+        let mut pilot = strategy(name, width, height)?;
+        let summary = strategy::benchmark(
+            pilot.as_mut(),
+            width,
+            height,
+            first_seed..first_seed + games,
+            max_ticks,
+        );
+        println!(
+            "{:<14} {:>6} {:>10.1} {:>10.1} {:>6} {:>9}",
+            summary.strategy,
+            summary.games,
+            summary.average_score,
+            summary.average_ticks,
+            summary.wins,
+            summary.timeouts
+        );
     }
-    
+
+    Ok(())
 }
 
+//the high scores at `path` and where to save them. A file that cannot be
+//read is no reason not to play: it is moved to `<file>.bak` and a new table
+//is started. If it cannot be moved either, this run saves no scores.
+fn load_scores(path: PathBuf) -> (Option<PathBuf>, HighScores) {
+    let error = match HighScores::load(&path, scores::DEFAULT_CAPACITY) {
+        Ok(high_scores) => return (Some(path), high_scores),
+        Err(error) => error,
+    };
 
-fn main() {
-    
+    let mut backup = path.clone().into_os_string();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    let empty = HighScores::new(scores::DEFAULT_CAPACITY);
+
+    match fs::rename(&path, &backup) {
+        Ok(()) => {
+            eprintln!(
+                "warning: could not read the high scores in {} ({}), moved them to {} and started a new table",
+                path.display(),
+                error,
+                backup.display()
+            );
+            (Some(path), empty)
+        }
+        Err(rename_error) => {
+            eprintln!(
+                "warning: could not read the high scores in {} ({}) nor move them aside ({}), no scores are saved this time",
+                path.display(),
+                error,
+                rename_error
+            );
+            (None, empty)
+        }
+    }
+}
+
+fn run() -> Result<Option<SnakeGame>, Box<dyn Error>> {
     let width: u16 = 30;
     let height: u16 = 20;
 
+    //`--replay <file>` plays back a game saved with `--record`.
+    if let Some(path) = arg_value("--replay") {
+        let replay = Replay::load(path)?;
+        return Ok(Some(watch(&replay)?));
+    }
+
+    //`--benchmark <games>` plays that many games per strategy without a
+    //screen, seeded from `--seed` on, each cut off after `--max-ticks`.
+    if let Some(games) = arg_value("--benchmark") {
+        let first_seed = arg_value("--seed").map_or(Ok(0), |seed| seed.parse())?;
+        let max_ticks = arg_value("--max-ticks").map_or(Ok(1_000_000), |ticks| ticks.parse())?;
+        benchmark(games.parse()?, first_seed, max_ticks, width, height)?;
+        return Ok(None);
+    }
 
-    let mut game = SnakeGame::new(width, height);
+    //`--seed <n>` replays the same food placement of the first game. Without
+    //one a seed is drawn, so the game can still be recorded.
+    let mut seed = match arg_value("--seed") {
+        Some(seed) => seed.parse()?,
+        None => rand::random(),
+    };
 
-    terminal::enable_raw_mode().unwrap(); //by default, terminals are in cooked mode. In raw mode,
-                                          //the inputs like the arrow keys are handled by the
-                                          //program as opposed to the normal termina.
-    execute!(stdout(), terminal::EnterAlternateScreen).unwrap(); //enter an alternate state so that
-                                                                 //when the program exits, the
-                                                                 //original terminal remains the
-                                                                 //same.
-    let mut last_update = Instant::now();
+    //without a mode on the command line the start menu asks for one.
+    let mut modes = modes();
+    let mut chosen = match mode_from_args()? {
+        Some(mode) => {
+            modes.insert(0, mode);
+            Some(0)
+        }
+        None => None,
+    };
 
-    while !game.game_over {
-        
-        if event::poll(Duration::from_millis(50)).unwrap() {
-            if let Event::Key(key) = event::read().unwrap() {
-                match key.code {
-                    KeyCode::Up => game.change_direction(Direction::Up),
-                    KeyCode::Down => game.change_direction(Direction::Down),
-                    KeyCode::Left => game.change_direction(Direction::Left),
-                    KeyCode::Right => game.change_direction(Direction::Right),
-                    KeyCode::Char('q') => break,
-                    _ => {}
-                }
-            }
+    //`--autopilot <strategy>` lets a strategy play instead of the keyboard.
+    //`--players <1|2>` share the keyboard, `--ai <n>` adds snakes steered by
+    //the shortest path strategy. Only games of a single player at the
+    //keyboard count towards the high scores.
+    let autopilot = arg_value("--autopilot");
+    if let Some(name) = &autopilot {
+        strategy(name, width, height)?;
+    }
+    let humans: usize = arg_value("--players").map_or(Ok(1), |players| players.parse())?;
+    if !(1..=2).contains(&humans) {
+        return Err("--players takes 1 or 2".into());
+    }
+    let ai: usize = arg_value("--ai").map_or(Ok(0), |ai| ai.parse())?;
+
+    //`--scores <file>` keeps the high scores somewhere other than the data
+    //directory, `--name <name>` is the name offered for them.
+    let scores_path = arg_value("--scores")
+        .map(PathBuf::from)
+        .or_else(HighScores::default_path);
+    let (scores_path, mut high_scores) = match scores_path {
+        Some(path) => load_scores(path),
+        None => (None, HighScores::default()),
+    };
+    let player = arg_value("--name")
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .unwrap_or_else(|| "player".to_string());
+
+    //`--connect <host:port>` joins a game served by `snake-server` instead.
+    if let Some(address) = arg_value("--connect") {
+        return Ok(Some(play_online(&address, &player)?));
+    }
+
+    let _screen = Screen::enter()?;
+    let mut renderer = TerminalRenderer::new(BufWriter::new(stdout()));
+
+    loop {
+        let index = match chosen {
+            Some(index) => index,
+            None => match menu(&mut renderer, &modes, 0)? {
+                Some(index) => index,
+                None => return Ok(None),
+            },
+        };
+        chosen = Some(index);
+
+        let (width, height, rules) = modes[index].setup(width, height);
+        let mut pilots: Pilots = Vec::new();
+        for _ in 0..humans {
+            pilots.push(match &autopilot {
+                Some(name) => Some(strategy(name, width, height)?),
+                None => None,
+            });
+        }
+        for _ in 0..ai {
+            pilots.push(Some(Box::new(ShortestPath)));
         }
 
-        if last_update.elapsed() >= Duration::from_millis(200) {
-            game.update();
-            game.render();
-            last_update = Instant::now();
+        let mut replay = Replay {
+            players: pilots.len(),
+            ..Replay::with_rules(seed, width, height, rules)
+        };
+        let mut game = replay.start();
+        let started = Instant::now();
+        play(&mut renderer, &mut game, &mut replay, &mut pilots)?;
+        let duration = started.elapsed();
+
+        //`--record <file>` saves the inputs of the last game for `--replay`.
+        if let Some(path) = arg_value("--record") {
+            replay.save(path)?;
+        }
+
+        //quit halfway: the game does not count.
+        if !game.game_over {
+            return Ok(Some(game));
+        }
+
+        let mut rank = None;
+        if let [None] = &pilots[..] {
+            let score = game.snakes[0].score;
+            let name = if high_scores.qualifies(score) {
+                let lines = render::game_over_lines(&game, duration, &high_scores, None);
+                ask_name(&mut renderer, &lines, &player)?
+            } else {
+                player.clone()
+            };
+
+            let entry = ScoreEntry::new(&name, score, game.snakes[0].len(), duration);
+            rank = high_scores.record(entry);
+            if let Some(path) = &scores_path {
+                high_scores.save(path)?;
+            }
         }
-    }
 
-    terminal::disable_raw_mode().unwrap();
-    execute!(stdout(), terminal::LeaveAlternateScreen, cursor::Show).unwrap();
-    
+        renderer.show(&render::game_over_lines(
+            &game,
+            duration,
+            &high_scores,
+            rank,
+        ))?;
+        match wait_for_next()? {
+            Next::Again => {}
+            Next::Menu => chosen = None,
+            Next::Quit => return Ok(Some(game)),
+        }
 
-    if game.game_over {
-        println!("Game Over! Score: {}", game.score);
+        seed = rand::random();
     }
+}
 
+fn main() {
+    match run() {
+        Ok(Some(game)) if game.snakes.len() > 1 && game.game_over => match game.winner() {
+            Some(player) => println!("Player {} Wins! Score: {}", player + 1, game.top_score()),
+            None => println!("Draw!"),
+        },
+        Ok(Some(game)) if game.won => println!("You Win! Score: {}", game.top_score()),
+        Ok(Some(game)) if game.ticks_left() == Some(0) => {
+            println!("Time Up! Score: {}", game.top_score())
+        }
+        Ok(Some(game)) if game.game_over => println!("Game Over! Score: {}", game.top_score()),
+        Ok(_) => {}
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,316 @@
+//! Game modes: the rules a game is played by on top of the classic ones,
+//! and levels, maps with walls inside the board.
+//!
+//! A level is a text file drawn the way the game is shown. `#` on the outer
+//! ring is the border, `#` inside it an obstacle, and anything else open
+//! floor. The snake starts in the middle, which has to be open:
+//!
+//! ```text
+//! ##########
+//! #        #
+//! #  ####  #
+//! #        #
+//! ##########
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::game::Position;
+
+/// Length of a tick at the start of a game.
+pub const BASE_TICK: Duration = Duration::from_millis(200);
+
+/// Shortest a tick gets with the speed ramp on.
+pub const MIN_TICK: Duration = Duration::from_millis(60);
+
+/// How much shorter a tick gets per point scored with the speed ramp on.
+pub const TICK_STEP: Duration = Duration::from_millis(5);
+
+/// How long a timed game lasts unless told otherwise.
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(120);
+
+/// Rules on top of the classic ones. The default plays the classic game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rules {
+    /// Leaving the board on one side comes back in on the other instead of
+    /// hitting the border.
+    pub wrap: bool,
+    /// Walls inside the border.
+    pub obstacles: HashSet<Position>,
+    /// Ticks get shorter as the score rises.
+    pub speed_ramp: bool,
+    /// The game ends after this many ticks.
+    pub time_limit: Option<u64>,
+}
+
+impl Rules {
+    /// How long the tick after reaching `score` lasts.
+    pub fn tick_duration(&self, score: u16) -> Duration {
+        if !self.speed_ramp {
+            return BASE_TICK;
+        }
+
+        BASE_TICK
+            .saturating_sub(TICK_STEP * u32::from(score))
+            .max(MIN_TICK)
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    /// A line that does not fit the map.
+    Format {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(error) => write!(f, "could not read level: {}", error),
+            LevelError::Format { line, message } => {
+                write!(f, "malformed level, line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self {
+        LevelError::Io(error)
+    }
+}
+
+/// A board with walls inside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Level {
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+    pub obstacles: HashSet<Position>,
+}
+
+//levels shipped with the game.
+const BUILTIN_LEVELS: [(&str, &str); 3] = [
+    ("box", include_str!("../levels/box.txt")),
+    ("pillars", include_str!("../levels/pillars.txt")),
+    ("maze", include_str!("../levels/maze.txt")),
+];
+
+impl Level {
+    pub fn parse(name: &str, text: &str) -> Result<Self, LevelError> {
+        //files from Windows end their lines in "\r\n". Blank lines around the
+        //map are fine, inside it they would shift the rows below.
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect();
+        let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(0);
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(first, |last| last + 1);
+        let rows = &lines[first..end];
+        let invalid = |y: usize, message: &str| LevelError::Format {
+            line: first + y + 1,
+            message: message.to_string(),
+        };
+
+        let columns = rows.first().map_or(0, |row| row.chars().count());
+        if rows.len() < 3 || columns < 3 {
+            return Err(invalid(0, "a level is at least 3 by 3"));
+        }
+        let width = u16::try_from(columns - 1).map_err(|_| invalid(0, "too wide"))?;
+        let height = u16::try_from(rows.len() - 1).map_err(|_| invalid(0, "too high"))?;
+
+        let mut obstacles = HashSet::new();
+        for (y, row) in rows.iter().enumerate() {
+            if row.is_empty() {
+                return Err(invalid(y, "blank row inside the map"));
+            }
+            if row.chars().count() != columns {
+                return Err(invalid(y, "rows differ in length"));
+            }
+
+            for (x, cell) in row.chars().enumerate() {
+                let position = Position {
+                    x: x as u16,
+                    y: y as u16,
+                };
+                let border = position.x == 0
+                    || position.y == 0
+                    || position.x == width
+                    || position.y == height;
+
+                match (cell, border) {
+                    ('#', true) => {}
+                    (_, true) => return Err(invalid(y, "the border has a gap")),
+                    ('#', false) => {
+                        obstacles.insert(position);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let start = Position {
+            x: width / 2,
+            y: height / 2,
+        };
+        if obstacles.contains(&start) {
+            return Err(invalid(usize::from(start.y), "the middle is walled up"));
+        }
+
+        Ok(Level {
+            name: name.to_string(),
+            width,
+            height,
+            obstacles,
+        })
+    }
+
+    /// Reads a level file, named after the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map_or("level".into(), |stem| stem.to_string_lossy());
+        Level::parse(&name, &fs::read_to_string(path)?)
+    }
+
+    /// The levels that come with the game.
+    pub fn builtin() -> Vec<Level> {
+        BUILTIN_LEVELS
+            .iter()
+            .map(|(name, text)| Level::parse(name, text).expect("built in levels are valid"))
+            .collect()
+    }
+
+    /// The built in level called `name`, or else the level file at that path.
+    pub fn find(name: &str) -> Result<Self, LevelError> {
+        match Level::builtin()
+            .into_iter()
+            .find(|level| level.name == name)
+        {
+            Some(level) => Ok(level),
+            None => Level::load(name),
+        }
+    }
+}
+
+/// A way to play, as picked from the start menu or the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Classic,
+    /// The border lets the snake through to the other side.
+    Wrap,
+    /// Faster with every point.
+    Speed,
+    /// As much as possible in the given time.
+    Timed(Duration),
+    Level(Level),
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Classic => write!(f, "classic"),
+            Mode::Wrap => write!(f, "wrap-around"),
+            Mode::Speed => write!(f, "speed ramp"),
+            Mode::Timed(limit) => write!(f, "timed, {} s", limit.as_secs()),
+            Mode::Level(level) => write!(f, "level: {}", level.name),
+        }
+    }
+}
+
+impl Mode {
+    /// The mode called `name` on the command line: classic, wrap, speed or
+    /// timed, the last one lasting `time_limit`.
+    pub fn named(name: &str, time_limit: Duration) -> Option<Mode> {
+        match name {
+            "classic" => Some(Mode::Classic),
+            "wrap" => Some(Mode::Wrap),
+            "speed" => Some(Mode::Speed),
+            "timed" => Some(Mode::Timed(time_limit)),
+            _ => None,
+        }
+    }
+
+    /// Board size and rules of the mode. Levels bring their own size, the
+    /// other modes play on `width` by `height`.
+    pub fn setup(&self, width: u16, height: u16) -> (u16, u16, Rules) {
+        let mut rules = Rules::default();
+
+        match self {
+            Mode::Classic => {}
+            Mode::Wrap => rules.wrap = true,
+            Mode::Speed => rules.speed_ramp = true,
+            Mode::Timed(limit) => {
+                rules.time_limit = Some((limit.as_millis() / BASE_TICK.as_millis()) as u64);
+            }
+            Mode::Level(level) => {
+                rules.obstacles = level.obstacles.clone();
+                return (level.width, level.height, rules);
+            }
+        }
+
+        (width, height, rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_obstacles_inside_the_border() {
+        let level = Level::parse("small", "######\n# ## #\n#    #\n#    #\n######\n").unwrap();
+
+        assert_eq!((level.width, level.height), (5, 4));
+        assert_eq!(
+            level.obstacles,
+            HashSet::from([Position { x: 2, y: 1 }, Position { x: 3, y: 1 }])
+        );
+    }
+
+    #[test]
+    fn rejects_a_border_with_a_gap() {
+        let error = Level::parse("leaky", "#####\n#   #\n#    \n#####\n").unwrap_err();
+        assert!(matches!(error, LevelError::Format { line: 3, .. }));
+    }
+
+    #[test]
+    fn reads_windows_line_endings() {
+        //the last line cut off before its "\n".
+        let text = "######\r\n# ## #\r\n#    #\r\n#    #\r\n######\r";
+        let level = Level::parse("small", text).unwrap();
+
+        assert_eq!((level.width, level.height), (5, 4));
+        assert_eq!(level.obstacles.len(), 2);
+    }
+
+    #[test]
+    fn rejects_blank_and_ragged_rows() {
+        let blank = "\n#####\n#   #\n\n#   #\n#####\n\n";
+        let error = Level::parse("gappy", blank).unwrap_err();
+        assert!(matches!(error, LevelError::Format { line: 4, .. }));
+
+        let ragged = "#####\n#   #\n#    #\n#####\n";
+        let error = Level::parse("ragged", ragged).unwrap_err();
+        assert!(matches!(error, LevelError::Format { line: 3, .. }));
+    }
+
+    #[test]
+    fn builtin_levels_are_valid() {
+        assert_eq!(Level::builtin().len(), BUILTIN_LEVELS.len());
+    }
+}
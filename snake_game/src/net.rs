@@ -0,0 +1,1013 @@
+//! Playing over the network. A `Server` runs the one true game on its own
+//! clock, every player joins it with a `Client`, sends their turns and keeps
+//! a copy of the game up to date from what the server sends back.
+//!
+//! Messages travel over TCP as frames: the length of the message in bytes,
+//! a 4 byte big endian number, followed by the message as text, one entry
+//! per line. A client opens with its protocol version and name, then only
+//! sends turns:
+//!
+//! ```text
+//! hello 1 alice
+//! input Up
+//! ```
+//!
+//! The server answers with `welcome <player>`, the player the client
+//! steers, and once everyone has joined sends the whole game:
+//!
+//! ```text
+//! game
+//! tick 0
+//! size 30 20
+//! wrap
+//! obstacle 7 5
+//! snake Right 0 alive 7,6
+//! snake Left 0 alive 23,13
+//! food regular 12 4 -
+//! ```
+//!
+//! After that only what every step changed: per snake its heading, score,
+//! state, length and new head, or `-` if it did not move, and all the food:
+//!
+//! ```text
+//! step
+//! tick 13
+//! snake Up 1 alive 2 7,9
+//! snake Left 0 wall 1 -
+//! food bonus 20 2 52
+//! over
+//! ```
+//!
+//! The server's clock decides when a turn counts: every step takes at most
+//! one turn per player, in the order they came in, and the rest waits for
+//! the steps after. Turns sent faster than the game steps are delayed, but
+//! none is lost.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::food::{Food, FoodKind, FoodPolicy};
+use crate::game::{Collision, Direction, Position, Snake, SnakeGame};
+use crate::mode::Rules;
+use crate::replay::Replay;
+
+/// Version sent in the hello. The server turns away clients of another one.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Port the server listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Longest frame accepted, anything longer is taken for garbage.
+pub const MAX_FRAME_LENGTH: usize = 1 << 20;
+
+/// Turns held per player at most. Any more are dropped.
+pub const MAX_BUFFERED_INPUTS: usize = 16;
+
+/// How long a new connection has to say hello.
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// A message that is not part of the protocol.
+    Protocol(String),
+    /// The server turned the client away, and why.
+    Refused(String),
+    /// The other side hung up.
+    Closed,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::Io(error) => write!(f, "network error: {}", error),
+            NetError::Protocol(message) => write!(f, "protocol error: {}", message),
+            NetError::Refused(reason) => write!(f, "refused by the server: {}", reason),
+            NetError::Closed => write!(f, "connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(error: io::Error) -> Self {
+        NetError::Io(error)
+    }
+}
+
+fn protocol(message: impl Into<String>) -> NetError {
+    NetError::Protocol(message.into())
+}
+
+/// Sends `message` as one frame.
+pub fn write_frame<W: Write>(out: &mut W, message: &str) -> io::Result<()> {
+    if message.len() > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "message too long for a frame",
+        ));
+    }
+
+    out.write_all(&(message.len() as u32).to_be_bytes())?;
+    out.write_all(message.as_bytes())?;
+    out.flush()
+}
+
+/// Reads the next frame. `None` if the connection was closed instead.
+pub fn read_frame<R: Read>(input: &mut R) -> Result<Option<String>, NetError> {
+    let mut length = [0; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(protocol(format!("frame of {} bytes", length)));
+    }
+    let mut message = vec![0; length];
+    input.read_exact(&mut message)?;
+
+    String::from_utf8(message)
+        .map(Some)
+        .map_err(|_| protocol("frame is not UTF-8"))
+}
+
+/// What a client tells the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Asks to join, first thing on a new connection.
+    Hello { version: u32, name: String },
+    /// A turn for the player's snake.
+    Input { direction: Direction },
+}
+
+impl ClientMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            ClientMessage::Hello { version, name } => format!("hello {} {}", version, name),
+            ClientMessage::Input { direction } => format!("input {}", direction),
+        }
+    }
+
+    pub fn decode(message: &str) -> Result<Self, NetError> {
+        //the name is the rest of the line, spaces and all.
+        match message.trim_end().splitn(3, ' ').collect::<Vec<_>>()[..] {
+            ["hello", version, name] => Ok(ClientMessage::Hello {
+                version: number(version, "version")?,
+                name: name.to_string(),
+            }),
+            ["input", direction] => Ok(ClientMessage::Input {
+                direction: parse_direction(direction)?,
+            }),
+            _ => Err(protocol(format!("unknown message {:?}", message))),
+        }
+    }
+}
+
+/// What a step changed for one snake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeDelta {
+    pub direction: Direction,
+    pub score: u16,
+    pub death: Option<Collision>,
+    /// Length after the step. The tail is cut down to it.
+    pub length: usize,
+    /// Where the head moved to, `None` if the snake did not move.
+    pub head: Option<Position>,
+}
+
+/// What a step changed, enough to bring a copy of the game from the tick
+/// before up to `tick`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta {
+    pub tick: u64,
+    pub snakes: Vec<SnakeDelta>,
+    /// All the food after the step.
+    pub foods: Vec<Food>,
+    pub game_over: bool,
+    pub won: bool,
+}
+
+impl Delta {
+    /// The changes from `before` to `after`, one step later.
+    pub fn between(before: &SnakeGame, after: &SnakeGame) -> Self {
+        let snakes = before
+            .snakes
+            .iter()
+            .zip(&after.snakes)
+            .map(|(before, after)| SnakeDelta {
+                direction: after.direction,
+                score: after.score,
+                death: after.death,
+                length: after.len(),
+                head: after.head().filter(|head| Some(*head) != before.head()),
+            })
+            .collect();
+
+        Delta {
+            tick: after.tick,
+            snakes,
+            foods: after.foods.clone(),
+            game_over: after.game_over,
+            won: after.won,
+        }
+    }
+
+    /// Brings `game` up to this step. It has to be at the tick before.
+    pub fn apply(&self, game: &mut SnakeGame) -> Result<(), NetError> {
+        if self.tick != game.tick + 1 {
+            return Err(protocol(format!(
+                "step to tick {} on a game at tick {}",
+                self.tick, game.tick
+            )));
+        }
+        if self.snakes.len() != game.snakes.len() {
+            return Err(protocol(format!(
+                "step for {} snakes on a game of {}",
+                self.snakes.len(),
+                game.snakes.len()
+            )));
+        }
+
+        for (snake, delta) in game.snakes.iter_mut().zip(&self.snakes) {
+            if let Some(head) = delta.head {
+                snake.body.push_front(head);
+            }
+            snake.body.truncate(delta.length);
+            snake.direction = delta.direction;
+            snake.score = delta.score;
+            snake.death = delta.death;
+        }
+        game.foods = self.foods.clone();
+        game.tick = self.tick;
+        game.game_over = self.game_over;
+        game.won = self.won;
+
+        Ok(())
+    }
+}
+
+/// What the server tells a client.
+#[derive(Debug, Clone)]
+pub enum ServerMessage {
+    /// The client joined and steers this player.
+    Welcome { player: usize },
+    /// The client was turned away, and why.
+    Refused(String),
+    /// The whole game, sent as it starts.
+    Game(Box<SnakeGame>),
+    /// What a step changed.
+    Step(Delta),
+}
+
+impl ServerMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            ServerMessage::Welcome { player } => format!("welcome {}", player),
+            ServerMessage::Refused(reason) => format!("refused {}", reason),
+            ServerMessage::Game(game) => encode_game(game).join("\n"),
+            ServerMessage::Step(delta) => encode_delta(delta).join("\n"),
+        }
+    }
+
+    pub fn decode(message: &str) -> Result<Self, NetError> {
+        let mut lines = message.lines();
+        let first = lines.next().unwrap_or_default();
+
+        match first.split_once(' ').unwrap_or((first, "")) {
+            ("welcome", player) => Ok(ServerMessage::Welcome {
+                player: number(player, "player")?,
+            }),
+            ("refused", reason) => Ok(ServerMessage::Refused(reason.to_string())),
+            ("game", "") => Ok(ServerMessage::Game(Box::new(decode_game(lines)?))),
+            ("step", "") => Ok(ServerMessage::Step(decode_delta(lines)?)),
+            _ => Err(protocol(format!("unknown message {:?}", first))),
+        }
+    }
+}
+
+fn encode_game(game: &SnakeGame) -> Vec<String> {
+    let mut lines = vec![
+        "game".to_string(),
+        format!("tick {}", game.tick),
+        format!("size {} {}", game.width, game.height),
+    ];
+    let rules = &game.rules;
+    if rules.wrap {
+        lines.push("wrap".to_string());
+    }
+    if rules.speed_ramp {
+        lines.push("speed-ramp".to_string());
+    }
+    if let Some(limit) = rules.time_limit {
+        lines.push(format!("time-limit {}", limit));
+    }
+    for obstacle in &rules.obstacles {
+        lines.push(format!("obstacle {} {}", obstacle.x, obstacle.y));
+    }
+    for snake in &game.snakes {
+        let body: Vec<String> = snake.body.iter().map(|&segment| cell(segment)).collect();
+        lines.push(format!(
+            "snake {} {} {} {}",
+            snake.direction,
+            snake.score,
+            state(snake.death),
+            body.join(" ")
+        ));
+    }
+    lines.extend(game.foods.iter().map(encode_food));
+    if game.game_over {
+        lines.push("over".to_string());
+    }
+    if game.won {
+        lines.push("won".to_string());
+    }
+    lines
+}
+
+//the game a client plays along with. Its own randomness is never used, the
+//server decides where food goes.
+fn decode_game<'a>(lines: impl Iterator<Item = &'a str>) -> Result<SnakeGame, NetError> {
+    let mut tick = 0;
+    let mut size = None;
+    let mut rules = Rules::default();
+    let mut snakes = Vec::new();
+    let mut foods = Vec::new();
+    let (mut game_over, mut won) = (false, false);
+
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        match fields[..] {
+            [] => continue,
+            ["tick", value] => tick = number(value, "tick")?,
+            ["size", width, height] => {
+                size = Some((number(width, "width")?, number(height, "height")?));
+            }
+            ["wrap"] => rules.wrap = true,
+            ["speed-ramp"] => rules.speed_ramp = true,
+            ["time-limit", limit] => rules.time_limit = Some(number(limit, "time limit")?),
+            ["obstacle", x, y] => {
+                rules.obstacles.insert(Position {
+                    x: number(x, "obstacle")?,
+                    y: number(y, "obstacle")?,
+                });
+            }
+            ["snake", direction, score, death, ref body @ ..] if !body.is_empty() => {
+                snakes.push(Snake {
+                    body: body
+                        .iter()
+                        .map(|segment| parse_cell(segment))
+                        .collect::<Result<_, _>>()?,
+                    direction: parse_direction(direction)?,
+                    inputs: VecDeque::new(),
+                    score: number(score, "score")?,
+                    death: parse_state(death)?,
+                });
+            }
+            ["food", ..] => foods.push(decode_food(&fields)?),
+            ["over"] => game_over = true,
+            ["won"] => won = true,
+            _ => return Err(protocol(format!("unknown entry {:?}", line))),
+        }
+    }
+
+    let (width, height) = size
+        .filter(|&(width, height)| width >= 2 && height >= 2)
+        .ok_or_else(|| protocol("missing or too small size"))?;
+    if snakes.is_empty() {
+        return Err(protocol("a game without snakes"));
+    }
+
+    let mut game = SnakeGame::with_rules(
+        width,
+        height,
+        rules,
+        FoodPolicy::default(),
+        StdRng::seed_from_u64(0),
+    );
+    game.snakes = snakes;
+    game.foods = foods;
+    game.tick = tick;
+    game.game_over = game_over;
+    game.won = won;
+    Ok(game)
+}
+
+fn encode_delta(delta: &Delta) -> Vec<String> {
+    let mut lines = vec!["step".to_string(), format!("tick {}", delta.tick)];
+    for snake in &delta.snakes {
+        lines.push(format!(
+            "snake {} {} {} {} {}",
+            snake.direction,
+            snake.score,
+            state(snake.death),
+            snake.length,
+            snake.head.map_or("-".to_string(), cell)
+        ));
+    }
+    lines.extend(delta.foods.iter().map(encode_food));
+    if delta.game_over {
+        lines.push("over".to_string());
+    }
+    if delta.won {
+        lines.push("won".to_string());
+    }
+    lines
+}
+
+fn decode_delta<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Delta, NetError> {
+    let mut delta = Delta {
+        tick: 0,
+        snakes: Vec::new(),
+        foods: Vec::new(),
+        game_over: false,
+        won: false,
+    };
+
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        match fields[..] {
+            [] => continue,
+            ["tick", tick] => delta.tick = number(tick, "tick")?,
+            ["snake", direction, score, death, length, head] => {
+                delta.snakes.push(SnakeDelta {
+                    direction: parse_direction(direction)?,
+                    score: number(score, "score")?,
+                    death: parse_state(death)?,
+                    length: number(length, "length")?,
+                    head: match head {
+                        "-" => None,
+                        head => Some(parse_cell(head)?),
+                    },
+                });
+            }
+            ["food", ..] => delta.foods.push(decode_food(&fields)?),
+            ["over"] => delta.game_over = true,
+            ["won"] => delta.won = true,
+            _ => return Err(protocol(format!("unknown entry {:?}", line))),
+        }
+    }
+
+    Ok(delta)
+}
+
+fn encode_food(food: &Food) -> String {
+    let kind = match food.kind {
+        FoodKind::Regular => "regular",
+        FoodKind::Bonus => "bonus",
+    };
+    let expires = food
+        .expires_at
+        .map_or("-".to_string(), |tick| tick.to_string());
+    format!(
+        "food {} {} {} {}",
+        kind, food.position.x, food.position.y, expires
+    )
+}
+
+fn decode_food(fields: &[&str]) -> Result<Food, NetError> {
+    let ["food", kind, x, y, expires] = fields[..] else {
+        return Err(protocol(format!("bad food {:?}", fields.join(" "))));
+    };
+
+    Ok(Food {
+        position: Position {
+            x: number(x, "food")?,
+            y: number(y, "food")?,
+        },
+        kind: match kind {
+            "regular" => FoodKind::Regular,
+            "bonus" => FoodKind::Bonus,
+            _ => return Err(protocol(format!("unknown food {:?}", kind))),
+        },
+        expires_at: match expires {
+            "-" => None,
+            tick => Some(number(tick, "expiry")?),
+        },
+    })
+}
+
+//a cell as `x,y`.
+fn cell(position: Position) -> String {
+    format!("{},{}", position.x, position.y)
+}
+
+fn parse_cell(text: &str) -> Result<Position, NetError> {
+    let (x, y) = text
+        .split_once(',')
+        .ok_or_else(|| protocol(format!("bad cell {:?}", text)))?;
+    Ok(Position {
+        x: number(x, "cell")?,
+        y: number(y, "cell")?,
+    })
+}
+
+//`alive`, or what the snake died of.
+fn state(death: Option<Collision>) -> String {
+    match death {
+        None => "alive".to_string(),
+        Some(Collision::Wall) => "wall".to_string(),
+        Some(Collision::Obstacle) => "obstacle".to_string(),
+        Some(Collision::Itself) => "itself".to_string(),
+        Some(Collision::Snake(player)) => format!("snake:{}", player),
+        Some(Collision::HeadOn(player)) => format!("head-on:{}", player),
+    }
+}
+
+fn parse_state(text: &str) -> Result<Option<Collision>, NetError> {
+    let collision = match text.split_once(':') {
+        None if text == "alive" => return Ok(None),
+        None if text == "wall" => Collision::Wall,
+        None if text == "obstacle" => Collision::Obstacle,
+        None if text == "itself" => Collision::Itself,
+        Some(("snake", player)) => Collision::Snake(number(player, "player")?),
+        Some(("head-on", player)) => Collision::HeadOn(number(player, "player")?),
+        _ => return Err(protocol(format!("unknown state {:?}", text))),
+    };
+    Ok(Some(collision))
+}
+
+fn parse_direction(text: &str) -> Result<Direction, NetError> {
+    text.parse().map_err(NetError::Protocol)
+}
+
+fn number<T: FromStr>(text: &str, what: &str) -> Result<T, NetError> {
+    text.parse()
+        .map_err(|_| protocol(format!("bad {} {:?}", what, text)))
+}
+
+//a turn from a player, or `None` once they are gone.
+type Turn = (usize, Option<Direction>);
+
+/// Serves a game to the clients that join it. The game starts once every
+/// player has joined and is stepped on the server's clock; the snake of a
+/// player who leaves goes on straight ahead.
+pub struct Server {
+    /// The game served, recorded as it is played. It waits for as many
+    /// clients as the replay has players.
+    pub replay: Replay,
+    /// Length of a tick. `None` follows the rules, like a local game.
+    pub tick: Option<Duration>,
+    listener: TcpListener,
+    //a client's stream, `None` once they are gone.
+    clients: Vec<Option<TcpStream>>,
+    sender: Sender<Turn>,
+    turns: Receiver<Turn>,
+}
+
+impl Server {
+    pub fn new(listener: TcpListener, replay: Replay) -> Self {
+        let (sender, turns) = mpsc::channel();
+
+        Server {
+            replay,
+            tick: None,
+            listener,
+            clients: Vec::new(),
+            sender,
+            turns,
+        }
+    }
+
+    /// Clients joined so far.
+    pub fn joined(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Waits for the next client to join and returns the player it steers
+    /// and its name. Connections that do not say hello properly are dropped
+    /// on the way.
+    pub fn accept(&mut self) -> Result<(usize, String), NetError> {
+        if self.joined() >= self.replay.players {
+            return Err(protocol("every player has joined already"));
+        }
+
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            if let Ok(name) = self.greet(&stream) {
+                let player = self.clients.len();
+                let reader = stream.try_clone()?;
+                let sender = self.sender.clone();
+                thread::spawn(move || forward_turns(reader, player, sender));
+                self.clients.push(Some(stream));
+                return Ok((player, name));
+            }
+        }
+    }
+
+    //reads the hello of a new connection and welcomes it.
+    fn greet(&self, mut stream: &TcpStream) -> Result<String, NetError> {
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+        let hello = read_frame(&mut stream)?.ok_or(NetError::Closed)?;
+        stream.set_read_timeout(None)?;
+
+        match ClientMessage::decode(&hello)? {
+            ClientMessage::Hello { version, name } if version == PROTOCOL_VERSION => {
+                let player = self.clients.len();
+                write_frame(&mut stream, &ServerMessage::Welcome { player }.encode())?;
+                Ok(name)
+            }
+            ClientMessage::Hello { version, .. } => {
+                let reason = format!(
+                    "client speaks protocol {}, the server {}",
+                    version, PROTOCOL_VERSION
+                );
+                write_frame(
+                    &mut stream,
+                    &ServerMessage::Refused(reason.clone()).encode(),
+                )?;
+                Err(NetError::Refused(reason))
+            }
+            ClientMessage::Input { .. } => Err(protocol("input before hello")),
+        }
+    }
+
+    /// Plays the game until it is over or every client has left, sending
+    /// each step to the clients still there.
+    pub fn run(&mut self) -> Result<SnakeGame, NetError> {
+        if self.joined() < self.replay.players {
+            return Err(protocol("not every player has joined yet"));
+        }
+
+        let mut game = self.replay.start();
+        self.broadcast(&ServerMessage::Game(Box::new(game.clone())));
+
+        let mut buffers = vec![VecDeque::new(); self.clients.len()];
+        let mut next_tick = Instant::now() + self.tick_length(&game);
+
+        while !game.game_over && self.clients.iter().any(Option::is_some) {
+            //gathers turns until the tick is up.
+            loop {
+                let timeout = next_tick.saturating_duration_since(Instant::now());
+                match self.turns.recv_timeout(timeout) {
+                    Ok((player, Some(turn))) => {
+                        let buffer: &mut VecDeque<_> = &mut buffers[player];
+                        if buffer.len() < MAX_BUFFERED_INPUTS {
+                            buffer.push_back(turn);
+                        }
+                    }
+                    Ok((player, None)) => self.clients[player] = None,
+                    Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            //one turn each, the most a step takes. Handing over more at once
+            //would overflow the queue of the game and lose the last ones.
+            for (player, buffer) in buffers.iter_mut().enumerate() {
+                if let Some(direction) = buffer.pop_front() {
+                    game.queue_input(player, direction);
+                    self.replay.record(game.tick, Some((player, direction)));
+                }
+            }
+
+            let before = game.clone();
+            self.replay.record(game.tick, None);
+            game.step();
+            self.broadcast(&ServerMessage::Step(Delta::between(&before, &game)));
+            next_tick = Instant::now() + self.tick_length(&game);
+        }
+
+        for client in self.clients.iter_mut().filter_map(Option::take) {
+            let _ = client.shutdown(Shutdown::Both);
+        }
+        Ok(game)
+    }
+
+    fn tick_length(&self, game: &SnakeGame) -> Duration {
+        self.tick
+            .unwrap_or_else(|| game.rules.tick_duration(game.top_score()))
+    }
+
+    //sends `message` to every client still there, dropping the ones that
+    //cannot be reached anymore.
+    fn broadcast(&mut self, message: &ServerMessage) {
+        let message = message.encode();
+
+        for client in &mut self.clients {
+            if let Some(stream) = client {
+                if write_frame(stream, &message).is_err() {
+                    *client = None;
+                }
+            }
+        }
+    }
+}
+
+//passes the turns of a client on to the game until it leaves.
+fn forward_turns(mut stream: TcpStream, player: usize, sender: Sender<Turn>) {
+    loop {
+        let turn = match read_frame(&mut stream) {
+            Ok(Some(message)) => match ClientMessage::decode(&message) {
+                Ok(ClientMessage::Input { direction }) => Some(direction),
+                //a stray hello or garbage: nothing to do with the game.
+                _ => continue,
+            },
+            Ok(None) | Err(_) => None,
+        };
+
+        let gone = turn.is_none();
+        if sender.send((player, turn)).is_err() || gone {
+            return;
+        }
+    }
+}
+
+/// One player's end of a served game.
+pub struct Client {
+    /// The player steered from here.
+    pub player: usize,
+    /// The game as of the last step received.
+    pub game: SnakeGame,
+    stream: TcpStream,
+    messages: Receiver<Result<ServerMessage, NetError>>,
+}
+
+impl Client {
+    /// Joins the game served at `address` as `name` and waits for it to
+    /// start, which is when every player has joined.
+    pub fn connect(address: impl ToSocketAddrs, name: &str) -> Result<Self, NetError> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let hello = ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: name.to_string(),
+        };
+        write_frame(&mut stream, &hello.encode())?;
+
+        let player = match receive(&mut stream)? {
+            ServerMessage::Welcome { player } => player,
+            ServerMessage::Refused(reason) => return Err(NetError::Refused(reason)),
+            _ => return Err(protocol("expected a welcome")),
+        };
+        let game = match receive(&mut stream)? {
+            ServerMessage::Game(game) => *game,
+            _ => return Err(protocol("expected the game")),
+        };
+        if player >= game.snakes.len() {
+            return Err(protocol(format!("no snake for player {}", player)));
+        }
+
+        let (sender, messages) = mpsc::channel();
+        let mut reader = stream.try_clone()?;
+        thread::spawn(move || loop {
+            let message = receive(&mut reader);
+            //nothing follows the last step.
+            let last = matches!(
+                message,
+                Err(_)
+                    | Ok(ServerMessage::Step(Delta {
+                        game_over: true,
+                        ..
+                    }))
+            );
+            if sender.send(message).is_err() || last {
+                return;
+            }
+        });
+
+        Ok(Client {
+            player,
+            game,
+            stream,
+            messages,
+        })
+    }
+
+    /// Sends a turn. The server takes it in the next step it has no earlier
+    /// turn of this player for.
+    pub fn send_input(&mut self, direction: Direction) -> Result<(), NetError> {
+        let input = ClientMessage::Input { direction };
+        write_frame(&mut self.stream, &input.encode())?;
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the server, then applies everything it
+    /// sent. Returns whether the game changed.
+    pub fn update(&mut self, timeout: Duration) -> Result<bool, NetError> {
+        let message = match self.messages.recv_timeout(timeout) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => return Ok(false),
+            Err(RecvTimeoutError::Disconnected) => return Err(NetError::Closed),
+        };
+        self.apply(message)?;
+
+        while let Ok(message) = self.messages.try_recv() {
+            self.apply(message)?;
+        }
+        Ok(true)
+    }
+
+    fn apply(&mut self, message: Result<ServerMessage, NetError>) -> Result<(), NetError> {
+        match message? {
+            ServerMessage::Step(delta) => delta.apply(&mut self.game),
+            ServerMessage::Game(game) => {
+                self.game = *game;
+                Ok(())
+            }
+            _ => Err(protocol("unexpected message during the game")),
+        }
+    }
+}
+
+//the next message from the server.
+fn receive(stream: &mut TcpStream) -> Result<ServerMessage, NetError> {
+    match read_frame(stream)? {
+        Some(message) => ServerMessage::decode(&message),
+        None => Err(NetError::Closed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_state(left: &SnakeGame, right: &SnakeGame) -> bool {
+        let bodies = |game: &SnakeGame| -> Vec<_> {
+            game.snakes
+                .iter()
+                .map(|snake| {
+                    (
+                        snake.body.clone(),
+                        snake.direction,
+                        snake.score,
+                        snake.death,
+                    )
+                })
+                .collect()
+        };
+
+        bodies(left) == bodies(right)
+            && left.foods == right.foods
+            && left.tick == right.tick
+            && left.game_over == right.game_over
+            && left.won == right.won
+            && left.rules == right.rules
+    }
+
+    #[test]
+    fn messages_round_trip_through_frames() {
+        let input = ClientMessage::Input {
+            direction: Direction::Up,
+        };
+        let hello = ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: "bob the builder".to_string(),
+        };
+
+        let mut wire = Vec::new();
+        write_frame(&mut wire, &hello.encode()).unwrap();
+        write_frame(&mut wire, &input.encode()).unwrap();
+        let mut wire = wire.as_slice();
+
+        let mut next = || ClientMessage::decode(&read_frame(&mut wire).unwrap().unwrap()).unwrap();
+        assert_eq!(next(), hello);
+        assert_eq!(next(), input);
+        assert!(read_frame(&mut wire).unwrap().is_none());
+    }
+
+    #[test]
+    fn steps_keep_a_copy_of_the_game_in_sync() {
+        let replay = Replay {
+            players: 2,
+            ..Replay::with_rules(
+                3,
+                16,
+                10,
+                Rules {
+                    wrap: true,
+                    ..Rules::default()
+                },
+            )
+        };
+        let mut game = replay.start();
+        let ServerMessage::Game(mut copy) =
+            ServerMessage::decode(&ServerMessage::Game(Box::new(game.clone())).encode()).unwrap()
+        else {
+            panic!("expected the game");
+        };
+        assert!(same_state(&game, &copy));
+
+        game.queue_input(0, Direction::Up);
+        while !game.game_over && game.tick < 100 {
+            let before = game.clone();
+            game.step();
+            let message = ServerMessage::Step(Delta::between(&before, &game)).encode();
+            let ServerMessage::Step(delta) = ServerMessage::decode(&message).unwrap() else {
+                panic!("expected a step");
+            };
+            delta.apply(&mut copy).unwrap();
+            assert!(same_state(&game, &copy));
+        }
+
+        //a step that was missed cannot be applied.
+        let stale = Delta::between(&game, &game);
+        assert!(stale.apply(&mut copy).is_err());
+    }
+
+    #[test]
+    fn clients_play_the_served_game_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let replay = Replay {
+            players: 2,
+            ..Replay::new(7, 20, 12)
+        };
+
+        let server = thread::spawn(move || {
+            let mut server = Server::new(listener, replay);
+            server.tick = Some(Duration::from_millis(20));
+            while server.joined() < 2 {
+                server.accept().unwrap();
+            }
+            let game = server.run().unwrap();
+            (game, server.replay)
+        });
+
+        //the first player heads for the top wall, the second goes on
+        //straight and outlives them.
+        let clients: Vec<_> = ["first", "second"]
+            .into_iter()
+            .map(|name| {
+                thread::spawn(move || {
+                    let mut client = Client::connect(address, name).unwrap();
+                    if client.player == 0 {
+                        client.send_input(Direction::Up).unwrap();
+                    }
+                    while !client.game.game_over {
+                        client.update(Duration::from_secs(1)).unwrap();
+                    }
+                    client
+                })
+            })
+            .collect();
+        let clients: Vec<Client> = clients.into_iter().map(|c| c.join().unwrap()).collect();
+        let (game, replay) = server.join().unwrap();
+
+        assert_eq!(game.winner(), Some(1));
+        assert_eq!(game.snakes[0].death, Some(Collision::Wall));
+        for client in &clients {
+            assert!(same_state(&client.game, &game));
+        }
+        assert!(same_state(&replay.play(|_, _| true), &game));
+    }
+
+    #[test]
+    fn turns_sent_within_one_tick_are_taken_one_per_step() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let mut server = Server::new(listener, Replay::new(7, 20, 12));
+            server.tick = Some(Duration::from_millis(20));
+            server.accept().unwrap();
+            let game = server.run().unwrap();
+            (game, server.replay)
+        });
+
+        //more turns at once than the game queues, a small loop ending upwards.
+        let turns = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ];
+        let mut client = Client::connect(address, "quick").unwrap();
+        for direction in turns {
+            client.send_input(direction).unwrap();
+        }
+        while !client.game.game_over {
+            client.update(Duration::from_secs(1)).unwrap();
+        }
+        let (game, replay) = server.join().unwrap();
+
+        let recorded: Vec<Direction> = replay.inputs.iter().map(|&(_, _, d)| d).collect();
+        assert_eq!(recorded, turns);
+        assert!(replay.inputs.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let mut taken = Vec::new();
+        replay.play(|game, _| {
+            taken.push(game.snakes[0].direction);
+            true
+        });
+        //the snake may have gone on right for a step or two before the first.
+        taken.dedup();
+        assert!(taken.ends_with(&turns), "took {:?}", taken);
+        assert_eq!(game.snakes[0].death, Some(Collision::Wall));
+        assert!(same_state(&client.game, &game));
+    }
+}
@@ -0,0 +1,274 @@
+use std::io;
+use std::time::Duration;
+
+use crate::food::FoodKind;
+use crate::game::SnakeGame;
+use crate::mode::BASE_TICK;
+use crate::scores::{self, HighScores};
+
+/// A front end showing the game. It is handed the whole game after every
+/// step and draws it however it likes.
+pub trait Renderer {
+    fn render(&mut self, game: &SnakeGame) -> io::Result<()>;
+}
+
+/// Characters the snakes of the players are drawn with, in turn.
+pub const SNAKE_CHARS: [char; 4] = ['o', '*', '+', '%'];
+
+/// The board as lines of text: `#` for the border and obstacles, `.` for a
+/// border the snakes wrap through, `o`, `*`, `+` and `%` for the snakes of
+/// the players and `x` for dead ones, `X` for food and `$` for bonus food,
+/// and the scores below.
+pub fn lines(game: &SnakeGame) -> Vec<String> {
+    let (width, height) = (usize::from(game.width), usize::from(game.height));
+    let mut rows = vec![vec![' '; width + 1]; height + 1];
+    let border = if game.rules.wrap { '.' } else { '#' };
+
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            if x == 0 || y == 0 || x == width || y == height {
+                *cell = border;
+            }
+        }
+    }
+
+    for obstacle in &game.rules.obstacles {
+        if let Some(cell) = rows
+            .get_mut(usize::from(obstacle.y))
+            .and_then(|row| row.get_mut(usize::from(obstacle.x)))
+        {
+            *cell = '#';
+        }
+    }
+
+    for (player, snake) in game.snakes.iter().enumerate() {
+        let symbol = if snake.is_alive() {
+            SNAKE_CHARS[player % SNAKE_CHARS.len()]
+        } else {
+            'x'
+        };
+
+        for segment in &snake.body {
+            if let Some(cell) = rows
+                .get_mut(usize::from(segment.y))
+                .and_then(|row| row.get_mut(usize::from(segment.x)))
+            {
+                *cell = symbol;
+            }
+        }
+    }
+
+    for food in &game.foods {
+        if let Some(cell) = rows
+            .get_mut(usize::from(food.position.y))
+            .and_then(|row| row.get_mut(usize::from(food.position.x)))
+        {
+            *cell = match food.kind {
+                FoodKind::Regular => 'X',
+                FoodKind::Bonus => '$',
+            };
+        }
+    }
+
+    let mut lines: Vec<String> = rows.into_iter().map(String::from_iter).collect();
+    let bonus = game
+        .foods
+        .iter()
+        .filter_map(|food| food.expires_at)
+        .min()
+        .map(|at| format!("  bonus {}", at.saturating_sub(game.tick)))
+        .unwrap_or_default();
+    //timed games count down in seconds at the base tick.
+    let time = game
+        .ticks_left()
+        .map(|ticks| {
+            let millis = ticks * BASE_TICK.as_millis() as u64;
+            format!("  time {}s", millis.div_ceil(1000))
+        })
+        .unwrap_or_default();
+    lines.push(format!("{}{}{}", scores_line(game), bonus, time));
+    lines
+}
+
+//the score, or with several snakes every player's score and snake.
+fn scores_line(game: &SnakeGame) -> String {
+    if let [snake] = &game.snakes[..] {
+        return format!("Score: {}", snake.score);
+    }
+
+    let players: Vec<String> = game
+        .snakes
+        .iter()
+        .enumerate()
+        .map(|(player, snake)| {
+            let symbol = if snake.is_alive() {
+                SNAKE_CHARS[player % SNAKE_CHARS.len()]
+            } else {
+                'x'
+            };
+            format!("P{} {}: {}", player + 1, symbol, snake.score)
+        })
+        .collect();
+    players.join("  ")
+}
+
+/// The screen shown once a game is over: how it went, the high score table
+/// with the game's place marked if it made it in, and the lifetime stats.
+pub fn game_over_lines(
+    game: &SnakeGame,
+    duration: Duration,
+    high_scores: &HighScores,
+    rank: Option<usize>,
+) -> Vec<String> {
+    let several = game.snakes.len() > 1;
+    let heading = match game.winner() {
+        _ if !several && game.won => "YOU WIN!".to_string(),
+        _ if !several && game.ticks_left() == Some(0) => "TIME UP".to_string(),
+        _ if !several => "GAME OVER".to_string(),
+        Some(player) => format!("PLAYER {} WINS!", player + 1),
+        None => "DRAW".to_string(),
+    };
+
+    let mut lines = vec![heading, String::new()];
+    for (player, snake) in game.snakes.iter().enumerate() {
+        let prefix = if several {
+            format!("Player {}: ", player + 1)
+        } else {
+            String::new()
+        };
+        lines.push(format!(
+            "{}Score: {}  length: {}  time: {}",
+            prefix,
+            snake.score,
+            snake.len(),
+            scores::format_duration(duration)
+        ));
+    }
+    if let Some(rank) = rank {
+        lines.push(format!("New high score, #{}!", rank + 1));
+    }
+
+    lines.push(String::new());
+    if high_scores.entries.is_empty() {
+        lines.push("No high scores yet.".to_string());
+    } else {
+        lines.push(format!(
+            "    {:<16} {:>5} {:>6} {:>6}  {}",
+            "name", "score", "length", "time", "date"
+        ));
+        for (index, entry) in high_scores.entries.iter().enumerate() {
+            lines.push(format!(
+                "{}{:>2} {:<16} {:>5} {:>6} {:>6}  {}",
+                if rank == Some(index) { '>' } else { ' ' },
+                index + 1,
+                entry.name,
+                entry.score,
+                entry.length,
+                scores::format_duration(entry.duration),
+                scores::format_date(entry.date)
+            ));
+        }
+    }
+
+    let stats = &high_scores.stats;
+    lines.push(String::new());
+    lines.push(format!(
+        "Games played: {}  average score: {:.1}  time played: {}",
+        stats.games_played,
+        stats.average_score(),
+        scores::format_duration(stats.time_played)
+    ));
+    lines.push(String::new());
+    lines.push("r: play again  m: menu  q: quit".to_string());
+    lines
+}
+
+/// The start menu, with the mode at `selected` marked.
+pub fn menu_lines(modes: &[String], selected: usize) -> Vec<String> {
+    let mut lines = vec!["SNAKE".to_string(), String::new()];
+    for (index, mode) in modes.iter().enumerate() {
+        let marker = if index == selected { '>' } else { ' ' };
+        lines.push(format!("{} {}", marker, mode));
+    }
+    lines.push(String::new());
+    lines.push("up/down: choose  enter: play  q: quit".to_string());
+    lines
+}
+
+/// Renders into memory instead of a terminal, for tests, CI and bots.
+#[derive(Debug, Clone, Default)]
+pub struct Headless {
+    /// Frames rendered so far.
+    pub frames: usize,
+    frame: String,
+}
+
+impl Headless {
+    pub fn new() -> Self {
+        Headless::default()
+    }
+
+    /// The last frame, one line per row.
+    pub fn frame(&self) -> &str {
+        &self.frame
+    }
+}
+
+impl Renderer for Headless {
+    fn render(&mut self, game: &SnakeGame) -> io::Result<()> {
+        self.frame = lines(game).join("\n");
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::Food;
+    use crate::game::{Direction, Position};
+
+    #[test]
+    fn headless_frames_follow_the_steps() {
+        let mut game = SnakeGame::with_seed(10, 6, 1);
+        game.food_policy.count = 0;
+        game.food_policy.bonus_chance = 0.0;
+        game.foods = vec![Food {
+            position: Position { x: 2, y: 1 },
+            kind: FoodKind::Regular,
+            expires_at: None,
+        }];
+
+        let mut renderer = Headless::new();
+        renderer.render(&game).unwrap();
+        game.step();
+        renderer.render(&game).unwrap();
+        game.queue_input(0, Direction::Down);
+        game.step();
+        renderer.render(&game).unwrap();
+
+        assert_eq!(renderer.frames, 3);
+        assert_eq!(
+            renderer.frame(),
+            [
+                "###########",
+                "# X       #",
+                "#         #",
+                "#         #",
+                "#     o   #",
+                "#         #",
+                "###########",
+                "Score: 0",
+            ]
+            .join("\n")
+        );
+
+        //two more steps run the snake into the border, where it stays, dead.
+        for _ in 0..2 {
+            game.step();
+            renderer.render(&game).unwrap();
+        }
+        assert!(game.game_over);
+        assert!(renderer.frame().contains("#     x   #\n###"));
+    }
+}
@@ -0,0 +1,376 @@
+//! Replays: the seed, size, rules, food policy and players of a game plus
+//! every input with the tick it was given at and the player it was for.
+//! Since all randomness comes from the seed, that is enough to play a game
+//! back exactly.
+//!
+//! The file is plain text, one entry per line. Rules and a food policy left
+//! at their default are not written. The food policy lists the regular food
+//! count and points, then the bonus chance, points and ticks:
+//!
+//! ```text
+//! snake-replay 4
+//! seed 42
+//! size 30 20
+//! players 2
+//! wrap
+//! speed-ramp
+//! time-limit 600
+//! obstacle 7 5
+//! food-policy 3 1 0.05 5 40
+//! 3 0 Up
+//! 7 1 Left
+//! ticks 25
+//! ```
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::food::FoodPolicy;
+use crate::game::{Direction, Events, Position, SnakeGame};
+use crate::mode::Rules;
+
+/// Format version written on the first line. Bump it whenever a change to
+/// the game makes older replays play out differently.
+pub const REPLAY_VERSION: u32 = 4;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// A line that is not part of the format.
+    Format {
+        line: usize,
+        message: String,
+    },
+    Version {
+        found: u32,
+        expected: u32,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "could not read replay: {}", error),
+            ReplayError::Format { line, message } => {
+                write!(f, "malformed replay, line {}: {}", line, message)
+            }
+            ReplayError::Version { found, expected } => write!(
+                f,
+                "replay has version {}, this build reads version {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+/// A recorded game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub rules: Rules,
+    pub food_policy: FoodPolicy,
+    /// Snakes in the game, human or not.
+    pub players: usize,
+    /// Inputs in the order they were given, each with the tick of the step
+    /// they were queued before and the player they were for.
+    pub inputs: Vec<(u64, usize, Direction)>,
+    /// Steps played, including the one that ended the game.
+    pub ticks: u64,
+}
+
+impl Replay {
+    /// An empty recording of a game started with `SnakeGame::with_seed`.
+    pub fn new(seed: u64, width: u16, height: u16) -> Self {
+        Replay::with_rules(seed, width, height, Rules::default())
+    }
+
+    /// An empty recording of a game seeded with `seed` and played by `rules`.
+    pub fn with_rules(seed: u64, width: u16, height: u16, rules: Rules) -> Self {
+        Replay {
+            seed,
+            width,
+            height,
+            rules,
+            food_policy: FoodPolicy::default(),
+            players: 1,
+            inputs: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    /// The game as it was before the first step.
+    pub fn start(&self) -> SnakeGame {
+        SnakeGame::with_players(
+            self.width,
+            self.height,
+            self.players,
+            self.rules.clone(),
+            self.food_policy,
+            StdRng::seed_from_u64(self.seed),
+        )
+    }
+
+    /// Notes an input for a player queued before the step at `tick`, or
+    /// with `None` that the step was taken.
+    pub fn record(&mut self, tick: u64, input: Option<(usize, Direction)>) {
+        match input {
+            Some((player, direction)) => self.inputs.push((tick, player, direction)),
+            None => self.ticks = self.ticks.max(tick + 1),
+        }
+    }
+
+    /// Plays the game back. `on_step` sees the game after every step and
+    /// stops the replay by returning `false`.
+    pub fn play<F>(&self, mut on_step: F) -> SnakeGame
+    where
+        F: FnMut(&SnakeGame, &Events) -> bool,
+    {
+        let mut game = self.start();
+        let mut inputs = self.inputs.iter().peekable();
+
+        while game.tick < self.ticks && !game.game_over {
+            let tick = game.tick;
+            while let Some((_, player, direction)) = inputs.next_if(|(at, _, _)| *at <= tick) {
+                game.queue_input(*player, *direction);
+            }
+
+            let events = game.step();
+            if !on_step(&game, &events) {
+                break;
+            }
+        }
+
+        game
+    }
+
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "snake-replay {}", REPLAY_VERSION)?;
+        writeln!(out, "seed {}", self.seed)?;
+        writeln!(out, "size {} {}", self.width, self.height)?;
+        writeln!(out, "players {}", self.players)?;
+        if self.rules.wrap {
+            writeln!(out, "wrap")?;
+        }
+        if self.rules.speed_ramp {
+            writeln!(out, "speed-ramp")?;
+        }
+        if let Some(limit) = self.rules.time_limit {
+            writeln!(out, "time-limit {}", limit)?;
+        }
+        //sorted, so saving the same game twice gives the same file.
+        let mut obstacles: Vec<&Position> = self.rules.obstacles.iter().collect();
+        obstacles.sort_by_key(|position| (position.y, position.x));
+        for position in obstacles {
+            writeln!(out, "obstacle {} {}", position.x, position.y)?;
+        }
+        if self.food_policy != FoodPolicy::default() {
+            let policy = &self.food_policy;
+            writeln!(
+                out,
+                "food-policy {} {} {} {} {}",
+                policy.count,
+                policy.points,
+                policy.bonus_chance,
+                policy.bonus_points,
+                policy.bonus_ticks
+            )?;
+        }
+        for (tick, player, direction) in &self.inputs {
+            writeln!(out, "{} {} {}", tick, player, direction)?;
+        }
+        writeln!(out, "ticks {}", self.ticks)?;
+
+        out.flush()
+    }
+
+    pub fn read<R: BufRead>(input: R) -> Result<Self, ReplayError> {
+        let mut replay = Replay::new(0, 0, 0);
+        let mut seen_header = false;
+
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let number = index + 1;
+            let invalid = |message: &str| ReplayError::Format {
+                line: number,
+                message: message.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match fields[..] {
+                [] => continue,
+                ["snake-replay", version] => {
+                    let found = version.parse().map_err(|_| invalid("bad version"))?;
+                    if found != REPLAY_VERSION {
+                        return Err(ReplayError::Version {
+                            found,
+                            expected: REPLAY_VERSION,
+                        });
+                    }
+                    seen_header = true;
+                }
+                _ if !seen_header => return Err(invalid("missing snake-replay header")),
+                ["seed", seed] => {
+                    replay.seed = seed.parse().map_err(|_| invalid("bad seed"))?;
+                }
+                ["size", width, height] => {
+                    replay.width = width.parse().map_err(|_| invalid("bad width"))?;
+                    replay.height = height.parse().map_err(|_| invalid("bad height"))?;
+                }
+                ["players", players] => {
+                    replay.players = players.parse().map_err(|_| invalid("bad player count"))?;
+                }
+                ["wrap"] => replay.rules.wrap = true,
+                ["speed-ramp"] => replay.rules.speed_ramp = true,
+                ["time-limit", limit] => {
+                    let limit = limit.parse().map_err(|_| invalid("bad time limit"))?;
+                    replay.rules.time_limit = Some(limit);
+                }
+                ["obstacle", x, y] => {
+                    replay.rules.obstacles.insert(Position {
+                        x: x.parse().map_err(|_| invalid("bad obstacle"))?,
+                        y: y.parse().map_err(|_| invalid("bad obstacle"))?,
+                    });
+                }
+                ["food-policy", count, points, bonus_chance, bonus_points, bonus_ticks] => {
+                    let bad = || invalid("bad food policy");
+                    let bonus_chance: f64 = bonus_chance.parse().map_err(|_| bad())?;
+                    if !(0.0..=1.0).contains(&bonus_chance) {
+                        return Err(invalid("bonus chance is not between 0 and 1"));
+                    }
+                    replay.food_policy = FoodPolicy {
+                        count: count.parse().map_err(|_| bad())?,
+                        points: points.parse().map_err(|_| bad())?,
+                        bonus_chance,
+                        bonus_points: bonus_points.parse().map_err(|_| bad())?,
+                        bonus_ticks: bonus_ticks.parse().map_err(|_| bad())?,
+                    };
+                }
+                ["ticks", ticks] => {
+                    replay.ticks = ticks.parse().map_err(|_| invalid("bad tick count"))?;
+                }
+                [tick, player, direction] => {
+                    let tick = tick.parse().map_err(|_| invalid("bad tick"))?;
+                    let player = player.parse().map_err(|_| invalid("bad player"))?;
+                    let direction = direction.parse().map_err(|error: String| invalid(&error))?;
+                    replay.inputs.push((tick, player, direction));
+                }
+                _ => return Err(invalid("unknown entry")),
+            }
+        }
+
+        if !seen_header {
+            return Err(ReplayError::Format {
+                line: 1,
+                message: "missing snake-replay header".to_string(),
+            });
+        }
+        if replay.width < 2 || replay.height < 2 {
+            return Err(ReplayError::Format {
+                line: 1,
+                message: "missing or too small size".to_string(),
+            });
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Replay::read(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_saved_replay_plays_back_the_recorded_game() {
+        let rules = Rules {
+            wrap: true,
+            obstacles: [Position { x: 4, y: 4 }, Position { x: 17, y: 9 }]
+                .into_iter()
+                .collect(),
+            time_limit: Some(50),
+            ..Rules::default()
+        };
+        let mut replay = Replay {
+            players: 2,
+            food_policy: FoodPolicy {
+                count: 3,
+                bonus_chance: 0.3,
+                ..FoodPolicy::default()
+            },
+            ..Replay::with_rules(21, 24, 16, rules)
+        };
+        let turns = [
+            (2, 0, Direction::Up),
+            (5, 1, Direction::Down),
+            (6, 0, Direction::Right),
+            (9, 1, Direction::Left),
+            (11, 0, Direction::Down),
+        ];
+
+        let mut game = replay.start();
+        assert_eq!(game.foods.len(), 3);
+        while !game.game_over && game.tick < 60 {
+            for &(tick, player, direction) in &turns {
+                if tick == game.tick {
+                    game.queue_input(player, direction);
+                    replay.record(game.tick, Some((player, direction)));
+                }
+            }
+            replay.record(game.tick, None);
+            game.step();
+        }
+
+        let mut text = Vec::new();
+        replay.write(&mut text).unwrap();
+        let loaded = Replay::read(text.as_slice()).unwrap();
+        assert_eq!(loaded, replay);
+
+        let played = loaded.play(|_, _| true);
+        assert_eq!(played.snakes, game.snakes);
+        assert_eq!(played.foods, game.foods);
+        assert_eq!(played.tick, game.tick);
+        assert_eq!(played.game_over, game.game_over);
+    }
+
+    #[test]
+    fn rejects_a_bonus_chance_out_of_range() {
+        let text = format!(
+            "snake-replay {}\nsize 20 20\nfood-policy 1 1 1.5 5 40\n",
+            REPLAY_VERSION
+        );
+        assert!(matches!(
+            Replay::read(text.as_bytes()),
+            Err(ReplayError::Format { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_another_version() {
+        let text = "snake-replay 0\nsize 20 20\n";
+        assert!(matches!(
+            Replay::read(text.as_bytes()),
+            Err(ReplayError::Version { found: 0, .. })
+        ));
+    }
+}
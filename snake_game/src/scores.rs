@@ -0,0 +1,376 @@
+//! The high score table and lifetime statistics, kept between runs in a
+//! plain text file, by default under the user's data directory:
+//!
+//! ```text
+//! snake-scores 1
+//! games 12
+//! total 148
+//! time 1803
+//! 31 32 94 1792310400 alice
+//! 24 25 71 1792224000 bob the builder
+//! ```
+//!
+//! Every entry holds score, length, duration in seconds and the time it was
+//! set in seconds since the Unix epoch, followed by the name.
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Format version written on the first line.
+pub const SCORES_VERSION: u32 = 1;
+
+/// Entries kept in the table by default.
+pub const DEFAULT_CAPACITY: usize = 10;
+
+/// Longest name kept. Longer ones are cut.
+pub const MAX_NAME_LENGTH: usize = 16;
+
+#[derive(Debug)]
+pub enum ScoresError {
+    Io(io::Error),
+    /// A line that is not part of the format.
+    Format {
+        line: usize,
+        message: String,
+    },
+    Version {
+        found: u32,
+        expected: u32,
+    },
+}
+
+impl fmt::Display for ScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoresError::Io(error) => write!(f, "could not read high scores: {}", error),
+            ScoresError::Format { line, message } => {
+                write!(f, "malformed high scores, line {}: {}", line, message)
+            }
+            ScoresError::Version { found, expected } => write!(
+                f,
+                "high scores have version {}, this build reads version {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScoresError {}
+
+impl From<io::Error> for ScoresError {
+    fn from(error: io::Error) -> Self {
+        ScoresError::Io(error)
+    }
+}
+
+/// A finished game in the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u16,
+    /// Length of the snake at the end.
+    pub length: usize,
+    pub duration: Duration,
+    /// Seconds since the Unix epoch.
+    pub date: u64,
+}
+
+impl ScoreEntry {
+    /// An entry dated now. The name is trimmed to one line of at most
+    /// `MAX_NAME_LENGTH` characters.
+    pub fn new(name: &str, score: u16, length: usize, duration: Duration) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        ScoreEntry {
+            name: clean_name(name),
+            score,
+            length,
+            duration,
+            date,
+        }
+    }
+}
+
+fn clean_name(name: &str) -> String {
+    let name: String = name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_NAME_LENGTH)
+        .collect();
+
+    if name.is_empty() {
+        "anonymous".to_string()
+    } else {
+        name
+    }
+}
+
+/// Totals over every game recorded, whether it made the table or not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub games_played: u64,
+    pub total_score: u64,
+    pub time_played: Duration,
+}
+
+impl Stats {
+    pub fn average_score(&self) -> f64 {
+        if self.games_played == 0 {
+            0.0
+        } else {
+            self.total_score as f64 / self.games_played as f64
+        }
+    }
+}
+
+/// The best games so far, highest score first, and the lifetime stats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScores {
+    pub entries: Vec<ScoreEntry>,
+    pub stats: Stats,
+    /// Entries kept at most.
+    pub capacity: usize,
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        HighScores::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl HighScores {
+    pub fn new(capacity: usize) -> Self {
+        HighScores {
+            entries: Vec::new(),
+            stats: Stats::default(),
+            capacity,
+        }
+    }
+
+    /// Where the table lives unless told otherwise, `None` on systems
+    /// without a data directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake_game").join("scores.txt"))
+    }
+
+    /// Whether a game with `score` would make it into the table.
+    pub fn qualifies(&self, score: u16) -> bool {
+        score > 0
+            && (self.entries.len() < self.capacity
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Counts a finished game in the stats and puts it in the table if it
+    /// qualifies. Returns its place, 0 for the top.
+    pub fn record(&mut self, entry: ScoreEntry) -> Option<usize> {
+        self.stats.games_played += 1;
+        self.stats.total_score += u64::from(entry.score);
+        self.stats.time_played += entry.duration;
+
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+
+        //below every entry with the same score: the earlier game keeps its place.
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(self.capacity);
+
+        Some(rank)
+    }
+
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "snake-scores {}", SCORES_VERSION)?;
+        writeln!(out, "games {}", self.stats.games_played)?;
+        writeln!(out, "total {}", self.stats.total_score)?;
+        writeln!(out, "time {}", self.stats.time_played.as_secs())?;
+        for entry in &self.entries {
+            writeln!(
+                out,
+                "{} {} {} {} {}",
+                entry.score,
+                entry.length,
+                entry.duration.as_secs(),
+                entry.date,
+                entry.name
+            )?;
+        }
+
+        out.flush()
+    }
+
+    pub fn read<R: BufRead>(input: R, capacity: usize) -> Result<Self, ScoresError> {
+        let mut scores = HighScores::new(capacity);
+        let mut seen_header = false;
+
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let number = index + 1;
+            let invalid = |message: &str| ScoresError::Format {
+                line: number,
+                message: message.to_string(),
+            };
+            let fields: Vec<&str> = line.splitn(5, ' ').collect();
+
+            match fields[..] {
+                [""] => continue,
+                ["snake-scores", version] => {
+                    let found = version.parse().map_err(|_| invalid("bad version"))?;
+                    if found != SCORES_VERSION {
+                        return Err(ScoresError::Version {
+                            found,
+                            expected: SCORES_VERSION,
+                        });
+                    }
+                    seen_header = true;
+                }
+                _ if !seen_header => return Err(invalid("missing snake-scores header")),
+                ["games", games] => {
+                    scores.stats.games_played =
+                        games.parse().map_err(|_| invalid("bad game count"))?;
+                }
+                ["total", total] => {
+                    scores.stats.total_score =
+                        total.parse().map_err(|_| invalid("bad total score"))?;
+                }
+                ["time", seconds] => {
+                    let seconds = seconds.parse().map_err(|_| invalid("bad time played"))?;
+                    scores.stats.time_played = Duration::from_secs(seconds);
+                }
+                [score, length, duration, date, name] => {
+                    let duration = duration.parse().map_err(|_| invalid("bad duration"))?;
+                    scores.entries.push(ScoreEntry {
+                        name: clean_name(name),
+                        score: score.parse().map_err(|_| invalid("bad score"))?,
+                        length: length.parse().map_err(|_| invalid("bad length"))?,
+                        duration: Duration::from_secs(duration),
+                        date: date.parse().map_err(|_| invalid("bad date"))?,
+                    });
+                }
+                _ => return Err(invalid("unknown entry")),
+            }
+        }
+
+        if !seen_header {
+            return Err(ScoresError::Format {
+                line: 1,
+                message: "missing snake-scores header".to_string(),
+            });
+        }
+
+        //a hand edited file may be out of order or longer than the table.
+        scores.entries.sort_by_key(|entry| Reverse(entry.score));
+        scores.entries.truncate(capacity);
+
+        Ok(scores)
+    }
+
+    /// Writes the table, creating its directory if need be.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Reads the table, or starts an empty one if there is no file yet.
+    pub fn load(path: impl AsRef<Path>, capacity: usize) -> Result<Self, ScoresError> {
+        match File::open(path) {
+            Ok(file) => HighScores::read(BufReader::new(file), capacity),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(HighScores::new(capacity)),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// `seconds` since the Unix epoch as a UTC date, e.g. `2026-10-18`.
+pub fn format_date(seconds: u64) -> String {
+    //days to civil date, after Howard Hinnant's algorithm.
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A duration as minutes and seconds, e.g. `3:07`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u16) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            score,
+            length: usize::from(score) + 1,
+            duration: Duration::from_secs(60),
+            date: 1_792_310_400,
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_scores_in_order() {
+        let mut scores = HighScores::new(3);
+
+        assert_eq!(scores.record(entry("a", 5)), Some(0));
+        assert_eq!(scores.record(entry("b", 9)), Some(0));
+        assert_eq!(scores.record(entry("c", 5)), Some(2));
+        assert_eq!(scores.record(entry("d", 4)), None);
+        assert_eq!(scores.record(entry("e", 7)), Some(1));
+
+        let names: Vec<&str> = scores.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["b", "e", "a"]);
+        assert_eq!(scores.stats.games_played, 5);
+        assert_eq!(scores.stats.total_score, 30);
+        assert_eq!(scores.stats.average_score(), 6.0);
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut scores = HighScores::new(DEFAULT_CAPACITY);
+        scores.record(entry("bob the builder", 12));
+        scores.record(entry("alice", 30));
+        scores.record(entry("nobody", 0));
+
+        let mut text = Vec::new();
+        scores.write(&mut text).unwrap();
+        let read = HighScores::read(text.as_slice(), DEFAULT_CAPACITY).unwrap();
+
+        assert_eq!(read, scores);
+    }
+
+    #[test]
+    fn formats_dates_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_792_310_400), "2026-10-18");
+    }
+}
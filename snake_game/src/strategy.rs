@@ -0,0 +1,514 @@
+//! Autopilots: strategies that pick a snake's next direction every tick,
+//! and a headless benchmark to compare them.
+
+use std::collections::VecDeque;
+
+use crate::game::{Direction, Position, SnakeGame};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Picks the direction a snake should take next.
+pub trait SnakeStrategy {
+    fn name(&self) -> &str;
+
+    /// The turn to queue for `player`'s snake before the coming step, `None`
+    /// to go straight on.
+    fn next_direction(&mut self, game: &SnakeGame, player: usize) -> Option<Direction>;
+}
+
+//open for the snake to move into, apart from the snake itself.
+fn inside(game: &SnakeGame, position: Position) -> bool {
+    game.wall_at(position).is_none()
+}
+
+//the game checks a move against whole snakes before any tail moves on, so
+//tails block as well, all but `player`'s own.
+fn blocked(game: &SnakeGame, player: usize, position: Position) -> bool {
+    game.snakes.iter().enumerate().any(|(owner, snake)| {
+        let leaving = if owner == player { 1 } else { 0 };
+        snake
+            .body
+            .range(..snake.len().saturating_sub(leaving))
+            .any(|segment| *segment == position)
+    })
+}
+
+//a cell the head of another snake alive could move into on the next step.
+fn contested(game: &SnakeGame, player: usize, position: Position) -> bool {
+    game.snakes
+        .iter()
+        .enumerate()
+        .filter(|(other, snake)| *other != player && snake.is_alive())
+        .filter_map(|(_, snake)| snake.head())
+        .any(|head| {
+            DIRECTIONS
+                .iter()
+                .any(|direction| game.ahead(head, *direction) == position)
+        })
+}
+
+/// Directions that do not run `player`'s snake into a wall or a body on the
+/// next step. Turning back is never one of them, the game ignores it. With
+/// other snakes about, directions another head could meet are left out as
+/// long as that leaves any.
+pub fn safe_directions(game: &SnakeGame, player: usize) -> Vec<Direction> {
+    let Some(snake) = game.snakes.get(player).filter(|snake| snake.is_alive()) else {
+        return Vec::new();
+    };
+    let Some(head) = snake.head() else {
+        return Vec::new();
+    };
+
+    let safe: Vec<Direction> = DIRECTIONS
+        .into_iter()
+        .filter(|direction| *direction != snake.direction.opposite())
+        .filter(|direction| {
+            let next = game.ahead(head, *direction);
+            inside(game, next) && !blocked(game, player, next)
+        })
+        .collect();
+
+    let uncontested: Vec<Direction> = safe
+        .iter()
+        .copied()
+        .filter(|direction| !contested(game, player, game.ahead(head, *direction)))
+        .collect();
+    if uncontested.is_empty() {
+        safe
+    } else {
+        uncontested
+    }
+}
+
+//number of free cells reachable from `start`, a measure of how roomy a move is.
+fn room(game: &SnakeGame, player: usize, start: Position) -> usize {
+    let index = |position: Position| {
+        usize::from(position.y) * usize::from(game.width + 1) + usize::from(position.x)
+    };
+    let mut seen = vec![false; usize::from(game.width + 1) * usize::from(game.height + 1)];
+    let mut queue = VecDeque::from([start]);
+    seen[index(start)] = true;
+    let mut count = 0;
+
+    while let Some(position) = queue.pop_front() {
+        count += 1;
+
+        for direction in DIRECTIONS {
+            let next = game.ahead(position, direction);
+            if inside(game, next) && !seen[index(next)] && !blocked(game, player, next) {
+                seen[index(next)] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    count
+}
+
+//the safe direction leading into the most room, to last as long as possible
+//when there is nothing better to do.
+fn roomiest(game: &SnakeGame, player: usize) -> Option<Direction> {
+    let head = game.snakes.get(player)?.head()?;
+
+    safe_directions(game, player)
+        .into_iter()
+        .max_by_key(|direction| room(game, player, game.ahead(head, *direction)))
+}
+
+/// Heads for the nearest food along a shortest path, found by a breadth
+/// first search around the body. Without a path it moves into the largest
+/// open area and waits for one to open up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShortestPath;
+
+impl SnakeStrategy for ShortestPath {
+    fn name(&self) -> &str {
+        "shortest-path"
+    }
+
+    fn next_direction(&mut self, game: &SnakeGame, player: usize) -> Option<Direction> {
+        let head = game.snakes.get(player)?.head()?;
+        let index = |position: Position| {
+            usize::from(position.y) * usize::from(game.width + 1) + usize::from(position.x)
+        };
+
+        //first step of the path to every cell reached.
+        let mut first: Vec<Option<Direction>> =
+            vec![None; usize::from(game.width + 1) * usize::from(game.height + 1)];
+        let mut queue = VecDeque::new();
+
+        for direction in safe_directions(game, player) {
+            let next = game.ahead(head, direction);
+            first[index(next)] = Some(direction);
+            queue.push_back(next);
+        }
+
+        while let Some(position) = queue.pop_front() {
+            if game.foods.iter().any(|food| food.position == position) {
+                return first[index(position)];
+            }
+
+            for direction in DIRECTIONS {
+                let next = game.ahead(position, direction);
+                if inside(game, next)
+                    && next != head
+                    && first[index(next)].is_none()
+                    && !blocked(game, player, next)
+                {
+                    first[index(next)] = first[index(position)];
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        roomiest(game, player)
+    }
+}
+
+/// Follows a fixed cycle through every cell of the board, so the snake
+/// never runs into itself and eventually eats everything.
+///
+/// A cycle through every cell exists when the inside of the board has an
+/// even number of cells. Otherwise the bottom right corner is left out and
+/// only visited, in place of a neighbour, when there is food on it. Once the
+/// snake is as long as the cycle it chases its own tail round it until it
+/// gets to the corner, the last free cell, and fills the board.
+#[derive(Debug, Clone)]
+pub struct Hamiltonian {
+    width: u16,
+    height: u16,
+    /// Direction to leave every cell in, indexed like `cell`.
+    next: Vec<Option<Direction>>,
+    /// The cell above a left out corner, and the corner.
+    detour: Option<(Position, Position)>,
+}
+
+impl Hamiltonian {
+    /// The cycle for a board of the given size. `None` if the inside is too
+    /// narrow for one.
+    pub fn new(width: u16, height: u16) -> Option<Self> {
+        let (columns, rows) = (width.checked_sub(1)?, height.checked_sub(1)?);
+        if columns < 2 || rows < 2 {
+            return None;
+        }
+
+        let mut strategy = Hamiltonian {
+            width,
+            height,
+            next: vec![None; usize::from(width + 1) * usize::from(height + 1)],
+            detour: None,
+        };
+
+        let path: Vec<(u16, u16)> = if rows.is_multiple_of(2) {
+            cycle(columns, rows)
+        } else if columns.is_multiple_of(2) {
+            cycle(rows, columns)
+                .into_iter()
+                .map(|(i, j)| (j, i))
+                .collect()
+        } else {
+            //the corner sits between the last two cells of the cycle above
+            //and to the left of it, and stands in for the one in between.
+            let corner = Position {
+                x: columns,
+                y: rows,
+            };
+            let above = Position {
+                x: columns,
+                y: rows - 1,
+            };
+            strategy.detour = Some((above, corner));
+            let cell = strategy.cell(corner);
+            strategy.next[cell] = Some(Direction::Left);
+
+            cycle_without_corner(columns, rows)?
+        };
+
+        for (index, &(i, j)) in path.iter().enumerate() {
+            let (ni, nj) = path[(index + 1) % path.len()];
+            let direction = match (i32::from(ni) - i32::from(i), i32::from(nj) - i32::from(j)) {
+                (1, 0) => Direction::Right,
+                (-1, 0) => Direction::Left,
+                (0, 1) => Direction::Down,
+                _ => Direction::Up,
+            };
+
+            let cell = strategy.cell(Position { x: i + 1, y: j + 1 });
+            strategy.next[cell] = Some(direction);
+        }
+
+        Some(strategy)
+    }
+
+    fn cell(&self, position: Position) -> usize {
+        usize::from(position.y) * usize::from(self.width + 1) + usize::from(position.x)
+    }
+}
+
+//a cycle through a `columns` x `rows` grid with an even number of rows:
+//back and forth along the rows, leaving out the first column, which is the
+//way back up.
+fn cycle(columns: u16, rows: u16) -> Vec<(u16, u16)> {
+    let mut path = vec![(0, 0)];
+
+    for j in 0..rows {
+        if j.is_multiple_of(2) {
+            path.extend((1..columns).map(|i| (i, j)));
+        } else {
+            path.extend((1..columns).rev().map(|i| (i, j)));
+        }
+    }
+    path.extend((1..rows).rev().map(|j| (0, j)));
+
+    path
+}
+
+//like `cycle` for an odd number of rows and columns, which has no cycle
+//through every cell. The last two rows are zigzagged through column by
+//column, leaving out the bottom right corner.
+fn cycle_without_corner(columns: u16, rows: u16) -> Option<Vec<(u16, u16)>> {
+    if columns < 3 || rows < 3 {
+        return None;
+    }
+
+    let mut path = vec![(0, 0)];
+    for j in 0..rows - 2 {
+        if j.is_multiple_of(2) {
+            path.extend((1..columns).map(|i| (i, j)));
+        } else {
+            path.extend((1..columns).rev().map(|i| (i, j)));
+        }
+    }
+
+    path.push((columns - 1, rows - 2));
+    for i in (1..columns - 1).rev() {
+        if (columns - 2 - i).is_multiple_of(2) {
+            path.extend([(i, rows - 2), (i, rows - 1)]);
+        } else {
+            path.extend([(i, rows - 1), (i, rows - 2)]);
+        }
+    }
+
+    path.push((0, rows - 1));
+    path.extend((1..rows - 1).rev().map(|j| (0, j)));
+
+    Some(path)
+}
+
+impl SnakeStrategy for Hamiltonian {
+    fn name(&self) -> &str {
+        "hamiltonian"
+    }
+
+    fn next_direction(&mut self, game: &SnakeGame, player: usize) -> Option<Direction> {
+        let head = game.snakes.get(player)?.head()?;
+
+        //off the cycle, e.g. a board of another size: just stay alive.
+        if game.width != self.width || game.height != self.height {
+            return roomiest(game, player);
+        }
+
+        let direction = match self.detour {
+            Some((above, corner))
+                if head == above && game.foods.iter().any(|food| food.position == corner) =>
+            {
+                Some(Direction::Down)
+            }
+            _ => self.next[self.cell(head)],
+        };
+
+        //a cycle running into an obstacle of a level is no help either.
+        match direction {
+            Some(direction) if safe_directions(game, player).contains(&direction) => {
+                Some(direction)
+            }
+            _ => roomiest(game, player),
+        }
+    }
+}
+
+/// Outcome of a batch of games played by one strategy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub strategy: String,
+    pub games: usize,
+    pub average_score: f64,
+    /// Average ticks survived.
+    pub average_ticks: f64,
+    pub wins: usize,
+    /// Games stopped at the tick limit, still running.
+    pub timeouts: usize,
+}
+
+/// Plays one seeded game per seed with the strategy, each for at most
+/// `max_ticks` ticks, without rendering anything.
+pub fn benchmark<S: SnakeStrategy + ?Sized>(
+    strategy: &mut S,
+    width: u16,
+    height: u16,
+    seeds: impl IntoIterator<Item = u64>,
+    max_ticks: u64,
+) -> Summary {
+    let (mut games, mut score, mut ticks, mut wins, mut timeouts) = (0, 0u64, 0u64, 0, 0);
+
+    for seed in seeds {
+        let mut game = SnakeGame::with_seed(width, height, seed);
+        while !game.game_over && game.tick < max_ticks {
+            if let Some(direction) = strategy.next_direction(&game, 0) {
+                game.queue_input(0, direction);
+            }
+            game.step();
+        }
+
+        games += 1;
+        score += u64::from(game.snakes[0].score);
+        ticks += game.tick;
+        if game.won {
+            wins += 1;
+        } else if !game.game_over {
+            timeouts += 1;
+        }
+    }
+
+    let average = |total: u64| {
+        if games == 0 {
+            0.0
+        } else {
+            total as f64 / games as f64
+        }
+    };
+
+    Summary {
+        strategy: strategy.name().to_string(),
+        games,
+        average_score: average(score),
+        average_ticks: average(ticks),
+        wins,
+        timeouts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::{Food, FoodKind};
+
+    //walks `path` as a cycle: every step goes to a neighbouring cell and
+    //every cell of the grid but `left_out` is visited exactly once.
+    fn assert_cycle(path: &[(u16, u16)], columns: u16, rows: u16, left_out: Option<(u16, u16)>) {
+        let mut seen = vec![false; usize::from(columns) * usize::from(rows)];
+        for (index, &(i, j)) in path.iter().enumerate() {
+            assert!(i < columns && j < rows, "({}, {}) is off the grid", i, j);
+            assert_ne!(Some((i, j)), left_out);
+            let cell = usize::from(j) * usize::from(columns) + usize::from(i);
+            assert!(!seen[cell], "({}, {}) visited twice", i, j);
+            seen[cell] = true;
+
+            let (ni, nj) = path[(index + 1) % path.len()];
+            assert_eq!(
+                i.abs_diff(ni) + j.abs_diff(nj),
+                1,
+                "({}, {}) to ({}, {})",
+                i,
+                j,
+                ni,
+                nj
+            );
+        }
+
+        let expected = usize::from(columns) * usize::from(rows) - usize::from(left_out.is_some());
+        assert_eq!(path.len(), expected);
+    }
+
+    fn food_at(game: &mut SnakeGame, x: u16, y: u16) {
+        game.foods = vec![Food {
+            position: Position { x, y },
+            kind: FoodKind::Regular,
+            expires_at: None,
+        }];
+    }
+
+    //goes straight on whatever happens.
+    struct Straight;
+
+    impl SnakeStrategy for Straight {
+        fn name(&self) -> &str {
+            "straight"
+        }
+
+        fn next_direction(&mut self, _: &SnakeGame, _: usize) -> Option<Direction> {
+            None
+        }
+    }
+
+    #[test]
+    fn cycles_visit_every_cell_once() {
+        for (columns, rows) in [(2, 2), (3, 2), (5, 4), (29, 18)] {
+            assert_cycle(&cycle(columns, rows), columns, rows, None);
+        }
+    }
+
+    #[test]
+    fn odd_cycles_visit_every_cell_but_the_corner() {
+        for (columns, rows) in [(3, 3), (5, 3), (3, 7), (29, 19)] {
+            let path = cycle_without_corner(columns, rows).unwrap();
+            assert_cycle(&path, columns, rows, Some((columns - 1, rows - 1)));
+        }
+        assert_eq!(cycle_without_corner(1, 5), None);
+    }
+
+    #[test]
+    fn shortest_path_takes_the_first_step_towards_the_food() {
+        let mut game = SnakeGame::with_seed(20, 20, 1);
+        game.food_policy.count = 0;
+        let head = game.snakes[0].head().unwrap();
+
+        food_at(&mut game, head.x + 1, head.y - 4);
+        assert_eq!(ShortestPath.next_direction(&game, 0), Some(Direction::Up));
+
+        //right behind: the way round starts with a turn.
+        food_at(&mut game, head.x - 3, head.y);
+        let direction = ShortestPath.next_direction(&game, 0);
+        assert!(matches!(direction, Some(Direction::Up | Direction::Down)));
+    }
+
+    #[test]
+    fn hamiltonian_fills_an_odd_by_odd_board() {
+        for seed in 0..3 {
+            let mut game = SnakeGame::with_seed(8, 6, seed);
+            let mut strategy = Hamiltonian::new(8, 6).unwrap();
+
+            while !game.game_over && game.tick < 100_000 {
+                if let Some(direction) = strategy.next_direction(&game, 0) {
+                    game.queue_input(0, direction);
+                }
+                game.step();
+            }
+
+            assert!(game.won, "seed {} ended at tick {}", seed, game.tick);
+            assert_eq!(game.snakes[0].len(), game.interior_cells());
+        }
+    }
+
+    #[test]
+    fn benchmark_counts_wins_and_timeouts() {
+        let mut hamiltonian = Hamiltonian::new(5, 5).unwrap();
+
+        let summary = benchmark(&mut hamiltonian, 5, 5, 0..4, 100_000);
+        assert_eq!((summary.games, summary.wins, summary.timeouts), (4, 4, 0));
+
+        let summary = benchmark(&mut hamiltonian, 5, 5, 0..4, 2);
+        assert_eq!((summary.games, summary.wins, summary.timeouts), (4, 0, 4));
+        assert_eq!(summary.average_ticks, 2.0);
+
+        //into the wall: neither won nor still running.
+        let summary = benchmark(&mut Straight, 20, 20, 0..4, 100_000);
+        assert_eq!((summary.games, summary.wins, summary.timeouts), (4, 0, 0));
+        assert_eq!(summary.strategy, "straight");
+    }
+}
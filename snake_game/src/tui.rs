@@ -0,0 +1,59 @@
+use crossterm::style::Print;
+use crossterm::{cursor, queue, terminal};
+use snake_game::{render, Renderer, SnakeGame};
+use std::io::{self, Write};
+
+/// Draws the game in the terminal. Every line of the board is rewritten in
+/// place and flushed once, so frames do not flicker.
+pub struct TerminalRenderer<W: Write> {
+    out: W,
+    cleared: bool,
+}
+
+impl<W: Write> TerminalRenderer<W> {
+    pub fn new(out: W) -> Self {
+        TerminalRenderer {
+            out,
+            cleared: false,
+        }
+    }
+
+    /// Draws `lines` from the top of the screen, wiping whatever the
+    /// previous screen left below them.
+    pub fn show(&mut self, lines: &[String]) -> io::Result<()> {
+        //clears the screen from the previous artifacts and leftovers once, later frames
+        //overwrite every cell they use.
+        if !self.cleared {
+            queue!(
+                self.out,
+                cursor::Hide,
+                terminal::Clear(terminal::ClearType::All)
+            )?;
+            self.cleared = true;
+        }
+
+        //in order to draw the elements in the terminal, we have to move the cursor to the
+        //location before printing out the desired element.
+        for (y, line) in lines.iter().enumerate() {
+            queue!(
+                self.out,
+                cursor::MoveTo(0, y as u16),
+                Print(line),
+                terminal::Clear(terminal::ClearType::UntilNewLine)
+            )?;
+        }
+        queue!(
+            self.out,
+            cursor::MoveTo(0, lines.len() as u16),
+            terminal::Clear(terminal::ClearType::FromCursorDown)
+        )?;
+
+        self.out.flush() //forces any pending buffer to be written to the terminal immedidately.
+    }
+}
+
+impl<W: Write> Renderer for TerminalRenderer<W> {
+    fn render(&mut self, game: &SnakeGame) -> io::Result<()> {
+        self.show(&render::lines(game))
+    }
+}